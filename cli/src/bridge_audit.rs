@@ -0,0 +1,91 @@
+//! The `bridge-audit` subcommand: reconcile the logs of the bridge contracts with the
+//! relay messages in the database of the node.
+
+use substrate_service::{self, FactoryFullConfiguration};
+use serde_json;
+use vendor::audit;
+use vendor::config::VendorSettings;
+use params::BridgeAuditCommand;
+use service;
+use error;
+use load_spec;
+
+/// Format of the `bridge-audit` report, `--format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+	/// One line per finding.
+	Table,
+	/// The whole report as a JSON object.
+	Json,
+}
+
+impl ::std::str::FromStr for ReportFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		match s {
+			"table" => Ok(ReportFormat::Table),
+			"json" => Ok(ReportFormat::Json),
+			_ => Err(format!("unknown report format {}, expected table or json", s)),
+		}
+	}
+}
+
+/// Run the `bridge-audit` subcommand and print its report.
+pub fn execute(command: BridgeAuditCommand) -> error::Result<()> {
+	if command.from > command.to {
+		return Err(format!("--from {} is after --to {}", command.from, command.to).into());
+	}
+	if let Some(chain_to) = command.chain_to {
+		if command.chain_from > chain_to {
+			return Err(format!("--chain-from {} is after --chain-to {}", command.chain_from, chain_to).into());
+		}
+	}
+	if let Some(ref tag) = command.eth_chain {
+		if tag != "kovan" && tag != "ropsten" {
+			return Err(format!("unknown chain {}, expected kovan or ropsten", tag).into());
+		}
+	}
+	let mut settings = match command.vendor_config {
+		Some(ref path) => VendorSettings::load(path)?,
+		None => VendorSettings::default(),
+	};
+	settings.set_rpc(&command.eth_rpc)?;
+
+	let mut transfers = Vec::new();
+	let mut releases = Vec::new();
+	let quorum = settings.quorum;
+	let chains = vec![("kovan", settings.kovan), ("ropsten", settings.ropsten)];
+	for (tag, chain) in chains {
+		if command.eth_chain.as_ref().map_or(false, |eth_chain| eth_chain != tag) {
+			continue;
+		}
+		let logs = audit::fetch_logs(&chain, quorum, command.from)
+			.map_err(|e| format!("cannot fetch the logs of {}: {}", tag, e))?;
+		let (chain_transfers, chain_releases) = audit::decode_logs(tag, &logs)
+			.map_err(|e| format!("cannot decode the logs of {}: {}", tag, e))?;
+		// releases are matched up to the head, transfers only in the audited range.
+		transfers.extend(chain_transfers.into_iter().filter(|transfer| transfer.block <= command.to));
+		releases.extend(chain_releases);
+	}
+
+	let spec = load_spec(&command.chain)?
+		.ok_or_else(|| format!("unknown chain spec {}", command.chain))?;
+	let mut config = FactoryFullConfiguration::<service::Factory>::default_with_spec(spec);
+	config.database_path = command.base_path
+		.join("chains")
+		.join(config.chain_spec.id())
+		.join("db")
+		.to_string_lossy()
+		.into();
+	let client = substrate_service::new_client::<service::Factory>(&config)
+		.map_err(|e| format!("cannot open the database at {}: {}", config.database_path, e))?;
+	let messages = audit::chain_transfers(&*client, command.chain_from, command.chain_to).map_err(|e| e.to_string())?;
+
+	let report = audit::reconcile(command.from, command.to, &transfers, &releases, &messages);
+	match command.format {
+		ReportFormat::Table => print!("{}", report.to_table()),
+		ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?),
+	}
+	Ok(())
+}
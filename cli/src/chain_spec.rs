@@ -4,7 +4,7 @@ use primitives::{Ed25519AuthorityId, ed25519};
 use node_primitives::AccountId;
 use node_runtime::{ConsensusConfig, CouncilSeatsConfig, CouncilVotingConfig, DemocracyConfig,
 	SessionConfig, StakingConfig, TimestampConfig, BalancesConfig, TreasuryConfig,
-	SudoConfig, ContractConfig, GrandpaConfig, IndicesConfig, Permill, Perbill, BankConfig};
+	SudoConfig, ContractConfig, GrandpaConfig, IndicesConfig, Permill, Perbill, BankConfig, MatrixConfig};
 pub use node_runtime::GenesisConfig;
 use substrate_service;
 
@@ -130,7 +130,20 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 			reward_balance_value: vec![1000,5000,60000,80000],
 			reward_balance_factor: vec![1,2,3,3],
 			total_despositing_balance:0,
-		})
+		}),
+		matrix: Some(matrix_genesis()),
+	}
+}
+
+/// Bridge contracts of the Kovan and Ropsten chain tags, those the vendor relays by default.
+/// relay keys are set by each validator with `set_relay_key`.
+fn matrix_genesis() -> MatrixConfig {
+	MatrixConfig {
+		bridge_contracts: vec![
+			(hex!["0000000000000000000000000000000000000000000000000000000000000001"].into(), hex!["D320e46E782417dc06A1F1a37B6d729D42639e0A"].to_vec()),
+			(hex!["0000000000000000000000000000000000000000000000000000000000000002"].into(), hex!["1836ad69e75dAe11fa8eEbEe8877F801c3CE0F6a"].to_vec()),
+		],
+		relay_keys: vec![],
 	}
 }
 
@@ -266,7 +279,8 @@ pub fn testnet_genesis(
 			reward_balance_value: vec![1000,5000,60000,80000],
 			reward_balance_factor: vec![1,2,3,4],
 			total_despositing_balance:0 ,
-		})
+		}),
+		matrix: Some(matrix_genesis()),
 	}
 }
 
@@ -31,6 +31,8 @@ extern crate sr_io as runtime_io;
 
 #[macro_use]
 extern crate log;
+extern crate env_logger;
+extern crate serde_json;
 extern crate structopt;
 extern crate vendor;
 extern crate signer;
@@ -39,12 +41,17 @@ pub use cli::error;
 pub mod chain_spec;
 mod service;
 mod params;
+mod relay_key;
+mod logging;
+mod bridge_audit;
 
 use tokio::prelude::Future;
 use tokio::runtime::Runtime;
 pub use cli::{VersionInfo, IntoExit};
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
-use params::{Params as NodeParams};
+use params::{Params as NodeParams, RelayKeyCommand, BridgeAuditCommand};
+use logging::LogFormat;
+use vendor::config::VendorSettings;
 use structopt::StructOpt;
 use std::ops::Deref;
 
@@ -96,6 +103,14 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
+	let args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+	if args.get(1).map_or(false, |arg| arg == "relay-key") {
+		return relay_key::execute(RelayKeyCommand::from_iter(&args[1..]));
+	}
+	if args.get(1).map_or(false, |arg| arg == "bridge-audit") {
+		return bridge_audit::execute(BridgeAuditCommand::from_iter(&args[1..]));
+	}
+
 	let full_version = substrate_service::config::full_version_from_strs(
 		version.version,
 		version.commit
@@ -111,11 +126,39 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 			Err(e) => e.exit(),
 		};
 
-    println!("#######{:?}", matches);
-	let (spec, config) = cli::parse_matches::<service::Factory, _>(
+	let (spec, mut config) = cli::parse_matches::<service::Factory, _>(
 		load_spec, &version, "substrate-node", &matches
 	)?;
-
+	let params = NodeParams::from_clap(&matches);
+	config.custom.relay_key = params.relay_key;
+	config.custom.relay_password = relay_key::read_password(&params.relay_password_file)?;
+	config.custom.relay_signer = params.relay_signer;
+	config.custom.relay_signer_auth = relay_key::signer_auth(&params.relay_signer_token_file, &params.relay_signer_ca)?;
+	config.custom.bridge_rpc_port = params.bridge_rpc_port;
+	config.custom.metrics_port = params.metrics_port;
+	config.custom.eth_key_file = params.eth_key_file;
+	config.custom.vendor = match (params.vendor, params.no_vendor) {
+		(true, _) => Some(true),
+		(_, true) => Some(false),
+		_ => None,
+	};
+	let mut vendor_settings = match params.vendor_config {
+		Some(ref path) => VendorSettings::load(path)?,
+		None => VendorSettings::default(),
+	};
+	vendor_settings.set_rpc(&params.eth_rpc)?;
+	if let Some(confirmations) = params.eth_confirmations {
+		vendor_settings.confirmations = confirmations;
+	}
+	config.custom.vendor_settings = vendor_settings;
+	config.custom.relay_dry_run = params.relay_dry_run;
+	config.custom.bridge_observer = params.bridge_observer;
+
+	// set before `execute_default`, which keeps a logger already set instead of its text one.
+	if params.log_format == LogFormat::Json {
+		logging::init_json(matches.value_of("log").unwrap_or(""))
+			.map_err(|err| format!("cannot set the json logger: {}", err))?;
+	}
 	match cli::execute_default::<service::Factory, _>(spec, exit, &matches, &config)? {
 		cli::Action::ExecutedInternally => (),
 		cli::Action::RunService(exit) => {
@@ -0,0 +1,166 @@
+//! Logging of the node as one JSON object per line, for log pipelines.
+
+use std::env;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+use env_logger::filter::{Builder, Filter};
+use log::{self, Log, Metadata, Record, LevelFilter};
+use serde_json::{Map, Value};
+
+/// Format of the node logs, `--log-format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+	/// The substrate text format.
+	Text,
+	/// One JSON object per line.
+	Json,
+}
+
+impl ::std::str::FromStr for LogFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		match s {
+			"text" => Ok(LogFormat::Text),
+			"json" => Ok(LogFormat::Json),
+			_ => Err(format!("unknown log format {}, expected text or json", s)),
+		}
+	}
+}
+
+struct JsonLogger {
+	filter: Filter,
+}
+
+/// Whether `key` is the name of a `key=value` field.
+fn is_field_name(key: &str) -> bool {
+	!key.is_empty() && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Start of the next ` key=` in `text` at or after `from`, and of its value.
+fn next_field(text: &str, from: usize) -> Option<(usize, usize)> {
+	let mut start = from;
+	while let Some(offset) = text[start..].find('=') {
+		let eq = start + offset;
+		let key_start = text[..eq].rfind(' ').map(|space| space + 1).unwrap_or(0);
+		if key_start >= from && (key_start == 0 || key_start > from) && is_field_name(&text[key_start..eq]) {
+			return Some((key_start, eq + 1));
+		}
+		start = eq + 1;
+	}
+	None
+}
+
+/// Split a log message into its text and its trailing `key=value` fields.
+/// values run up to the next field, so they may contain spaces.
+pub fn split_fields(message: &str) -> (String, Vec<(String, String)>) {
+	let mut fields = Vec::new();
+	let first = match next_field(message, 0) {
+		Some(first) => first,
+		None => return (message.to_string(), fields),
+	};
+	let text = message[..first.0].trim_right().to_string();
+	let mut current = first;
+	loop {
+		let key = message[current.0..current.1 - 1].to_string();
+		match next_field(message, current.1) {
+			Some(next) => {
+				fields.push((key, message[current.1..next.0].trim_right().to_string()));
+				current = next;
+			}
+			None => {
+				fields.push((key, message[current.1..].to_string()));
+				break;
+			}
+		}
+	}
+	(text, fields)
+}
+
+/// The JSON line of `record`, logged at `ts` milliseconds since the epoch.
+pub fn format_record(record: &Record, ts: u64) -> String {
+	let (message, fields) = split_fields(&record.args().to_string());
+	let mut object = Map::new();
+	object.insert("ts".into(), Value::from(ts));
+	object.insert("level".into(), Value::from(record.level().to_string()));
+	object.insert("target".into(), Value::from(record.target()));
+	object.insert("message".into(), Value::from(message));
+	if !fields.is_empty() {
+		let fields = fields.into_iter().map(|(key, value)| (key, Value::from(value))).collect();
+		object.insert("fields".into(), Value::Object(fields));
+	}
+	Value::Object(object).to_string()
+}
+
+impl Log for JsonLogger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		self.filter.enabled(metadata)
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.filter.matches(record) {
+			return;
+		}
+		let ts = SystemTime::now().duration_since(UNIX_EPOCH)
+			.map(|elapsed| elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()))
+			.unwrap_or(0);
+		let stderr = io::stderr();
+		let _ = writeln!(stderr.lock(), "{}", format_record(record, ts));
+	}
+
+	fn flush(&self) {}
+}
+
+/// Log as JSON lines to stderr, filtered like the text logs by `RUST_LOG`
+/// and the `--log` `pattern`.
+pub fn init_json(pattern: &str) -> Result<(), log::SetLoggerError> {
+	let mut builder = Builder::new();
+	builder.filter(None, LevelFilter::Info);
+	if let Ok(lvl) = env::var("RUST_LOG") {
+		builder.parse(&lvl);
+	}
+	builder.parse(pattern);
+	let filter = builder.build();
+	log::set_max_level(filter.filter());
+	log::set_boxed_logger(Box::new(JsonLogger { filter }))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use log::Level;
+
+	#[test]
+	fn test_split_fields() {
+		let (text, fields) = split_fields("sent release transaction chain=ETH_Kovan kind=ingress nonce=5 tx_hash=0x01");
+		assert_eq!(text, "sent release transaction");
+		assert_eq!(fields, vec![
+			("chain".to_string(), "ETH_Kovan".to_string()),
+			("kind".to_string(), "ingress".to_string()),
+			("nonce".to_string(), "5".to_string()),
+			("tx_hash".to_string(), "0x01".to_string()),
+		]);
+
+		let (text, fields) = split_fields("request failed chain=ETH_Kovan error=connection refused (os error 111)");
+		assert_eq!(text, "request failed");
+		assert_eq!(fields[1], ("error".to_string(), "connection refused (os error 111)".to_string()));
+
+		assert_eq!(split_fields("Imported #1 (0xab=cd)"), ("Imported #1 (0xab=cd)".to_string(), vec![]));
+	}
+
+	#[test]
+	fn test_format_record() {
+		let line = format_record(&Record::builder()
+			.args(format_args!("submitted kind=deposit nonce=3"))
+			.level(Level::Info)
+			.target("vendor::supervisor")
+			.build(), 1000);
+		let value: Value = ::serde_json::from_str(&line).unwrap();
+		assert_eq!(value["ts"], 1000);
+		assert_eq!(value["level"], "INFO");
+		assert_eq!(value["target"], "vendor::supervisor");
+		assert_eq!(value["message"], "submitted");
+		assert_eq!(value["fields"]["kind"], "deposit");
+		assert_eq!(value["fields"]["nonce"], "3");
+	}
+}
@@ -1,9 +1,170 @@
 use structopt::StructOpt;
 use cli::CoreParams;
+use std::path::PathBuf;
+use logging::LogFormat;
+use bridge_audit::ReportFormat;
 
 /// Extend params for Node
 #[derive(Debug, StructOpt)]
 pub struct Params {
+	/// Substrate account signing relay extrinsics, an ss58 address of a key in the keystore
+	#[structopt(long = "relay-key", value_name = "SS58")]
+	pub relay_key: Option<String>,
+
+	/// File with the password of the relay keys, empty if not set
+	#[structopt(long = "relay-password-file", value_name = "PATH", parse(from_os_str))]
+	pub relay_password_file: Option<PathBuf>,
+
+	/// Signing daemon holding the Ethereum relay key, `unix:<path>` or `https://<host>[:<port>][/<path>]`.
+	/// the key of the keystore is used if not set
+	#[structopt(long = "relay-signer", value_name = "URL")]
+	pub relay_signer: Option<String>,
+
+	/// File of the bearer token sent to a https relay signer
+	#[structopt(long = "relay-signer-token-file", value_name = "PATH", parse(from_os_str), requires = "relay-signer")]
+	pub relay_signer_token_file: Option<PathBuf>,
+
+	/// PEM certificate to trust for a https relay signer, on top of the system roots
+	#[structopt(long = "relay-signer-ca", value_name = "PATH", parse(from_os_str), requires = "relay-signer-token-file")]
+	pub relay_signer_ca: Option<PathBuf>,
+
+	/// Encrypted key file of the Ethereum relay key, instead of the one in the keystore
+	#[structopt(long = "eth-key-file", value_name = "PATH", parse(from_os_str), conflicts_with = "relay-signer")]
+	pub eth_key_file: Option<PathBuf>,
+
+	/// Start the vendor relaying bridge events, and fail if it can't. by default it
+	/// starts when a relay key is set
+	#[structopt(long = "vendor")]
+	pub vendor: bool,
+
+	/// Don't start the vendor
+	#[structopt(long = "no-vendor", conflicts_with = "vendor")]
+	pub no_vendor: bool,
+
+	/// TOML file with the Ethereum endpoints, bridge contracts and relay settings of the vendor
+	#[structopt(long = "vendor-config", value_name = "PATH", parse(from_os_str))]
+	pub vendor_config: Option<PathBuf>,
+
+	/// Ethereum rpc endpoint of a chain, `kovan=<url>` or `ropsten=<url>`, repeat it for more
+	/// endpoints. replaces the endpoints of the vendor config, `ws://` urls are subscribed to
+	#[structopt(long = "eth-rpc", value_name = "TAG=URL")]
+	pub eth_rpc: Vec<String>,
+
+	/// Number of Ethereum blocks on top of a block before its logs are relayed
+	#[structopt(long = "eth-confirmations", value_name = "BLOCKS")]
+	pub eth_confirmations: Option<u32>,
+
+	/// Log the relay extrinsics and Ethereum transactions instead of sending them, without saving relay progress
+	#[structopt(long = "relay-dry-run")]
+	pub relay_dry_run: bool,
+
+	/// Watch both sides of the bridge instead of relaying, and report relay messages in blocks
+	/// without an Ethereum log or logs never relayed. never signs or sends anything
+	#[structopt(long = "bridge-observer", conflicts_with_all = &["vendor", "relay-dry-run"])]
+	pub bridge_observer: bool,
+
+	/// Serve the `bridge_*` rpc over WebSocket on this port of localhost
+	#[structopt(long = "bridge-rpc-port", value_name = "PORT")]
+	pub bridge_rpc_port: Option<u16>,
+
+	/// Serve Prometheus metrics of the bridge on this port of localhost, at `/metrics`
+	#[structopt(long = "metrics-port", value_name = "PORT")]
+	pub metrics_port: Option<u16>,
+
+	/// Format of the logs, `text` or `json` with one object per line
+	#[structopt(long = "log-format", value_name = "FORMAT", default_value = "text")]
+	pub log_format: LogFormat,
+
 	#[structopt(flatten)]
 	core: CoreParams
 }
+
+/// Manage the secp256k1 key that signs relay messages for Ethereum
+#[derive(Debug, StructOpt)]
+#[structopt(name = "relay-key")]
+pub enum RelayKeyCommand {
+	/// Generate a new relay key into the keystore
+	#[structopt(name = "generate")]
+	Generate {
+		/// Keystore directory of the node
+		#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+		keystore_path: PathBuf,
+		/// File with the password to encrypt the key with
+		#[structopt(long = "password-file", value_name = "PATH", parse(from_os_str))]
+		password_file: Option<PathBuf>,
+	},
+
+	/// Import an existing relay key into the keystore
+	#[structopt(name = "import")]
+	Import {
+		/// File with the hex private key, read from stdin if not set
+		#[structopt(long = "secret-file", value_name = "PATH", parse(from_os_str))]
+		secret_file: Option<PathBuf>,
+		/// Keystore directory of the node
+		#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+		keystore_path: PathBuf,
+		/// File with the password to encrypt the key with
+		#[structopt(long = "password-file", value_name = "PATH", parse(from_os_str))]
+		password_file: Option<PathBuf>,
+	},
+
+	/// Print the proof `set_relay_key` expects that the relay key belongs to an account
+	#[structopt(name = "prove")]
+	Prove {
+		/// Ss58 address of the relay account
+		#[structopt(long = "account", value_name = "SS58")]
+		account: String,
+		/// Keystore directory of the node
+		#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+		keystore_path: PathBuf,
+		/// File with the password the key is encrypted with
+		#[structopt(long = "password-file", value_name = "PATH", parse(from_os_str))]
+		password_file: Option<PathBuf>,
+	},
+}
+
+/// Reconcile the logs of the bridge contracts in a range of Ethereum blocks with the
+/// relay messages in the database of the node, which may stay stopped
+#[derive(Debug, StructOpt)]
+#[structopt(name = "bridge-audit")]
+pub struct BridgeAuditCommand {
+	/// First Ethereum block audited
+	#[structopt(long = "from", value_name = "BLOCK")]
+	pub from: u64,
+
+	/// Last Ethereum block audited
+	#[structopt(long = "to", value_name = "BLOCK")]
+	pub to: u64,
+
+	/// Ethereum chain audited, `kovan` or `ropsten`. both if not set
+	#[structopt(long = "eth-chain", value_name = "TAG")]
+	pub eth_chain: Option<String>,
+
+	/// TOML file with the Ethereum endpoints and bridge contracts, as for the vendor
+	#[structopt(long = "vendor-config", value_name = "PATH", parse(from_os_str))]
+	pub vendor_config: Option<PathBuf>,
+
+	/// Ethereum rpc endpoint of a chain, `kovan=<url>` or `ropsten=<url>`, repeat it for more endpoints
+	#[structopt(long = "eth-rpc", value_name = "TAG=URL")]
+	pub eth_rpc: Vec<String>,
+
+	/// First chain block whose relay messages are audited
+	#[structopt(long = "chain-from", value_name = "BLOCK", default_value = "1")]
+	pub chain_from: u64,
+
+	/// Last chain block whose relay messages are audited, the best block if not set
+	#[structopt(long = "chain-to", value_name = "BLOCK")]
+	pub chain_to: Option<u64>,
+
+	/// Specification of the chain in the database
+	#[structopt(long = "chain", value_name = "CHAIN_SPEC", default_value = "")]
+	pub chain: String,
+
+	/// Base path of the node, as passed to it with `--base-path`
+	#[structopt(long = "base-path", short = "d", value_name = "PATH", parse(from_os_str))]
+	pub base_path: PathBuf,
+
+	/// Format of the report, `table` or `json`
+	#[structopt(long = "format", value_name = "FORMAT", default_value = "table")]
+	pub format: ReportFormat,
+}
@@ -0,0 +1,92 @@
+//! Relay keys: the Substrate account that signs relay extrinsics and the
+//! secp256k1 key that signs relay messages for Ethereum.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use primitives::hexdisplay::HexDisplay;
+use std::sync::Arc;
+use primitives::ed25519::{Pair, Public};
+use substrate_keystore::Store as Keystore;
+use signer::{keystore, remote, RemoteSigner, Signer};
+use params::RelayKeyCommand;
+use error;
+
+/// Read a password from `file`, without the trailing newline. no file is an empty password.
+pub fn read_password(file: &Option<PathBuf>) -> error::Result<String> {
+	match *file {
+		Some(ref path) => Ok(fs::read_to_string(path)?.trim_end_matches(|c| c == '\r' || c == '\n').to_string()),
+		None => Ok(String::new()),
+	}
+}
+
+/// Credentials of a https relay signer, from the token in `token_file` and the
+/// certificate in `ca`. none without a token.
+pub fn signer_auth(token_file: &Option<PathBuf>, ca: &Option<PathBuf>) -> error::Result<Option<remote::Auth>> {
+	let token = match *token_file {
+		Some(_) => read_password(token_file)?,
+		None => return Ok(None),
+	};
+	let auth = remote::Auth::new(token)?;
+	Ok(Some(match *ca {
+		Some(ref path) => auth.with_ca(fs::read(path)?)?,
+		None => auth,
+	}))
+}
+
+/// Run a `relay-key` subcommand.
+pub fn execute(command: RelayKeyCommand) -> error::Result<()> {
+	let pair = match command {
+		RelayKeyCommand::Generate { keystore_path, password_file } => {
+			let password = read_password(&password_file)?;
+			keystore::generate(&keystore_path, &password).map_err(|e| e.to_string())?
+		},
+		RelayKeyCommand::Import { secret_file, keystore_path, password_file } => {
+			let password = read_password(&password_file)?;
+			let mut secret = String::new();
+			match secret_file {
+				Some(path) => { fs::File::open(path)?.read_to_string(&mut secret)?; },
+				None => { io::stdin().read_to_string(&mut secret)?; },
+			}
+			keystore::import(&keystore_path, secret, &password)
+				.map_err(|e| format!("invalid relay key: {}", e))?
+		},
+		RelayKeyCommand::Prove { account, keystore_path, password_file } => {
+			let password = read_password(&password_file)?;
+			let public = Public::from_ss58check(&account)
+				.map_err(|e| format!("invalid --account {}: {:?}", account, e))?;
+			let pair = keystore::load(&keystore_path, &password).map_err(|e| e.to_string())?;
+			println!("relay key proof: 0x{}", HexDisplay::from(&pair.key_proof(&public.0)));
+			pair
+		},
+	};
+	println!("relay key address: {:?}", pair.address());
+	Ok(())
+}
+
+/// Load both relay keys. `relay_key` is the ss58 address of the Substrate account,
+/// the Ethereum key is held by the daemon at `signer`, with its credentials, if set,
+/// or read from `key_file` if set, or from the keystore.
+pub fn load(keystore: &Keystore, keystore_path: &Path, relay_key: &str, password: &str, signer: Option<(&str, Option<remote::Auth>)>, key_file: Option<&Path>) -> Result<(Pair, Arc<Signer>), String> {
+	let public = Public::from_ss58check(relay_key)
+		.map_err(|e| format!("invalid --relay-key {}: {:?}", relay_key, e))?;
+	let account = keystore.load(&public, password)
+		.map_err(|e| format!("relay account {} is not in the keystore: {:?}", relay_key, e))?;
+	let eth_key: Arc<Signer> = match signer {
+		Some((endpoint, auth)) => {
+			let endpoint = remote::Endpoint::from_str(endpoint)?;
+			let signer = RemoteSigner::connect(endpoint.clone(), auth)
+				.map_err(|e| format!("cannot reach the relay signer at {}: {}", endpoint, e))?;
+			info!(target: "vendor::supervisor", "relay key held by a remote signer address={:?} endpoint={}", signer.address(), endpoint);
+			Arc::new(signer)
+		},
+		None => match key_file {
+			Some(path) => Arc::new(keystore::load_file(path, password)
+				.map_err(|e| format!("cannot load the relay key file {}: {}", path.display(), e))?),
+			None => Arc::new(keystore::load(keystore_path, password)
+				.map_err(|e| format!("{}, create one with the `relay-key` subcommand", e))?),
+		},
+	};
+	Ok((account, eth_key))
+}
@@ -18,8 +18,12 @@ use substrate_service::{
 };
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use inherents::InherentDataProviders;
-use vendor::{start_vendor, VendorServiceConfig};
-use signer::Keyring;
+use vendor::{start_vendor, start_observer, PendingRelays, config::VendorSettings, health::HealthMonitor, metrics::{self, Metrics}, rpc::{self as bridge_rpc, Bridge}};
+use relay_key;
+use signer::remote::Auth;
+use std::path::{Path, PathBuf};
+use std::net::SocketAddr;
+use futures::{Future, Stream};
 
 construct_simple_protocol! {
 	/// Demo protocol attachment for substrate.
@@ -33,6 +37,28 @@ pub struct NodeConfig<F: substrate_service::ServiceFactory> {
 	// https://github.com/paritytech/substrate/issues/1134
 	pub grandpa_import_setup: Option<(Arc<grandpa::BlockImportForService<F>>, grandpa::LinkHalfForService<F>)>,
 	inherent_data_providers: InherentDataProviders,
+	/// ss58 address of the Substrate account signing relay extrinsics
+	pub relay_key: Option<String>,
+	/// Password of the relay keys
+	pub relay_password: String,
+	/// Endpoint of the signing daemon holding the Ethereum relay key
+	pub relay_signer: Option<String>,
+	/// Credentials of a https signing daemon
+	pub relay_signer_auth: Option<Auth>,
+	/// Localhost port of the `bridge_*` rpc, not served if not set
+	pub bridge_rpc_port: Option<u16>,
+	/// Localhost port of the Prometheus metrics, not served if not set
+	pub metrics_port: Option<u16>,
+	/// Key file of the Ethereum relay key, instead of the keystore
+	pub eth_key_file: Option<PathBuf>,
+	/// Whether to start the vendor, or only when a relay key is set if not set
+	pub vendor: Option<bool>,
+	/// Endpoints, contracts and relay settings of the vendor
+	pub vendor_settings: VendorSettings,
+	/// Log the relay extrinsics and Ethereum transactions instead of sending them
+	pub relay_dry_run: bool,
+	/// Check both sides of the bridge instead of starting the vendor
+	pub bridge_observer: bool,
 }
 
 impl<F> Default for NodeConfig<F> where F: substrate_service::ServiceFactory {
@@ -40,6 +66,17 @@ impl<F> Default for NodeConfig<F> where F: substrate_service::ServiceFactory {
 		NodeConfig {
 			grandpa_import_setup: None,
 			inherent_data_providers: InherentDataProviders::new(),
+			relay_key: None,
+			relay_password: String::new(),
+			relay_signer: None,
+			relay_signer_auth: None,
+			bridge_rpc_port: None,
+			metrics_port: None,
+			eth_key_file: None,
+			vendor: None,
+			vendor_settings: VendorSettings::default(),
+			relay_dry_run: false,
+			bridge_observer: false,
 		}
 	}
 }
@@ -62,6 +99,9 @@ mod vendor {
     use runtime_primitives::generic::{BlockId, Era};
     use runtime_primitives::codec::{Decode, Encode, Compact};
     use node_primitives::{AccountId, Index};
+    /// Number of blocks the mock extrinsics stay valid for.
+    const MOCK_ERA_PERIOD: u64 = 64;
+
     struct Mock{
         poll_interval: Interval,
     }
@@ -77,7 +117,7 @@ mod vendor {
                     Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
                     Ok(Async::Ready(Some(value))) => value,
                 };
-                println!("############");
+                trace!(target: "vendor::mock", "tick");
                 // do you things
                 return Ok(Async::Ready(Some(())));
             }
@@ -102,7 +142,7 @@ mod vendor {
 
         let key = keystore.load(&keystore.contents().unwrap()[0], "").unwrap();
         let local_id: AccountId = key.public().0.into();
-        println!("ROS account: {:?}", key.public().to_ss58check());
+        info!(target: "vendor::mock", "mock relayer account={}", key.public().to_ss58check());
         let timer_stream = Interval::new_interval(std::time::Duration::from_secs(10));
         let fork_client = client.clone();
         let send_stream = timer_stream.for_each(move |_| {
@@ -127,13 +167,15 @@ mod vendor {
                 // };
 
                 let block = fork_client.info().unwrap().best_number;
+                let era = Era::mortal(MOCK_ERA_PERIOD, block.as_());
+                let birth_hash = fork_client.block_number_to_hash(As::sa(era.birth(block.as_()))).unwrap();
                 let payload = (
                     Compact::<Index>::from(0),  // index/nonce
                     Call::Matrix(MatrixCall::ingress(vec![0, 1, 3, 4, 5, 6, 7],vec![1,0])), //function
                     Compact::<Index>::from(0),  // index/nonce
                     Call::Matrix(MatrixCall::ingress(vec![0, 1, 3, 4, 5, 6, 7], vec![0, 1, 3, 4, 5, 6, 7])), //function
-                    Era::immortal(),  
-                    fork_client.genesis_hash(),
+                    era,
+                    birth_hash,
                 );
                 let signature = key.sign(&payload.encode());
                 let extrinsic = UncheckedExtrinsic::new_signed(
@@ -145,7 +187,7 @@ mod vendor {
                 );
                 let xt: ExtrinsicFor<A> = Decode::decode(&mut &extrinsic.encode()[..]).unwrap();
                 //println!("check: {:?}", extrinsic.check());
-                println!("@@@@@@@@@@@result: {:?}", pool.submit_one(&BlockId::number(block), xt));
+                debug!(target: "vendor::mock", "submitted result={:?}", pool.submit_one(&BlockId::number(block), xt));
                 Ok(())
         }).map_err(|_| ());
 
@@ -154,7 +196,6 @@ mod vendor {
         let storage_stream = client.storage_changes_notification_stream(Some(&[events_key])).unwrap()
         .map(|(block, changes)| StorageChangeSet { block, changes: changes.iter().cloned().collect()})
         .for_each(move |change_set| {
-            println!("@@@@@@@@@@@@@@@@@@");
             let records: Vec<Vec<EventRecord<Event>>> = change_set.changes
                 .iter()
                 .filter_map(|(_, mbdata)| if let Some(StorageData(data)) = mbdata {
@@ -167,15 +208,15 @@ mod vendor {
                 .cloned()
                 .map(|r| r.event)
                 .collect();
-            println!("@@@@@@@@@@@@changes: {:?}", events);
+            debug!(target: "vendor::mock", "system events events={:?}", events);
             events.iter().for_each(|event| {
                 if let Event::sigcount(e) = event {
                     match e {
                         //RawEvent::Ingress(hash, msg) => println!("@@@@@@@@ Ingress: hash{:?}, msg{:?}", hash, msg),
-                        RawEvent::Txisok(transcation) => println!("XXXXXXXXXXX Txisok: hash{:?}",transcation),
-                        RawEvent::TranscationVerified(transcation,vec) => println!("XXXXXXXXXXX Txisok: hash{:?}",transcation) ,
+                        RawEvent::Txisok(transcation) => debug!(target: "vendor::mock", "Txisok tx_hash={:?}", transcation),
+                        RawEvent::TranscationVerified(transcation, _) => debug!(target: "vendor::mock", "TranscationVerified tx_hash={:?}", transcation),
                         // other events.
-                        _ => println!("@@@@@@@ other: {:?}", e),
+                        _ => debug!(target: "vendor::mock", "other event={:?}", e),
                     }
                 }
             });
@@ -188,7 +229,7 @@ mod vendor {
         .map(|_| ())
         .select(on_exit)
         .then(|_| {
-            println!("##############on exit");
+            debug!(target: "vendor::mock", "exiting");
             Ok(())
         })
     }
@@ -209,28 +250,81 @@ construct_service_factory! {
 		FullService = FullComponents<Self>
 			{ |config: FactoryFullConfiguration<Self>, executor: TaskExecutor| {
                 let db_path = config.database_path.clone();
-                let keyring = config.keys.first().map_or(Keyring::default(), |key| Keyring::from(key.as_bytes()));
-                info!("eth signer key: {}", keyring.to_hex());
-                match FullComponents::<Factory>::new(config, executor.clone()) {
-                    Ok(service) => {
-                        executor.spawn(start_vendor(
-                            VendorServiceConfig { kovan_url: "https://kovan.infura.io/v3/5b83a690fa934df09253dd2843983d89".to_string(),
-                                                  ropsten_url: "https://ropsten.infura.io/v3/5b83a690fa934df09253dd2843983d89".to_string(),
-                                                  kovan_address: "D320e46E782417dc06A1F1a37B6d729D42639e0A".to_string(),
-                                                  ropsten_address: "1836ad69e75dAe11fa8eEbEe8877F801c3CE0F6a".to_string(),
-                                                  db_path: db_path,
-                                                  eth_key: keyring.to_hex(), // sign message
-                                                },
-                            service.network(),
-                            service.client(),
-                            service.transaction_pool(),
-                            service.keystore(),
-                            service.on_exit(),
-                        ));
+                let keystore_path = config.keystore_path.clone();
+                let relay_key = config.custom.relay_key.clone();
+                let relay_password = config.custom.relay_password.clone();
+                let relay_signer = config.custom.relay_signer.clone();
+                let relay_signer_auth = config.custom.relay_signer_auth.clone();
+                let bridge_rpc_port = config.custom.bridge_rpc_port;
+                let metrics_port = config.custom.metrics_port;
+                let eth_key_file = config.custom.eth_key_file.clone();
+                let vendor = config.custom.vendor;
+                let vendor_settings = config.custom.vendor_settings.clone();
+                let relay_dry_run = config.custom.relay_dry_run;
+                let bridge_observer = config.custom.bridge_observer;
+                let service = FullComponents::<Factory>::new(config, executor.clone())?;
+                let health = HealthMonitor::default();
+                let pending = PendingRelays::default();
+                let metrics = Metrics::default();
+                let bridge = Bridge::new(service.client(), health.clone(), pending.clone(), executor.clone());
+                if let Some(port) = metrics_port {
+                    let (metrics, health, pending, bridge) = (metrics.clone(), health.clone(), pending.clone(), bridge.clone());
+                    let addr = metrics::serve(&SocketAddr::from(([127, 0, 0, 1], port)), move || {
+                        metrics.render(&health, &pending, &bridge.chain_gauges())
+                    }).map_err(|e| format!("cannot serve metrics on port {}: {}", port, e))?;
+                    info!(target: "vendor::metrics", "serving prometheus metrics url=http://{}/metrics", addr);
+                }
+                if let Some(port) = bridge_rpc_port {
+                    let server = bridge_rpc::start_ws(&SocketAddr::from(([127, 0, 0, 1], port)), bridge.clone())?;
+                    info!(target: "vendor::rpc", "serving the bridge rpc url=ws://127.0.0.1:{}", port);
+                    let notifications = service.client().import_notification_stream()
+                        .for_each(move |_| {
+                            bridge.notify_transfers();
+                            Ok(())
+                        });
+                    // the server stops when dropped, on exit.
+                    executor.spawn(notifications.select(service.on_exit()).then(move |_| {
+                        drop(server);
+                        Ok(())
+                    }));
+                }
+                if bridge_observer {
+                    info!(target: "vendor::observer", "observing the bridge, the vendor is not started");
+                    executor.spawn(start_observer(vendor_settings, db_path, health, metrics, service.client(), service.on_exit()));
+                    return Ok(service)
+                }
+                let relay_key = match (vendor, relay_key) {
+                    (Some(false), _) => {
+                        info!(target: "vendor::supervisor", "the vendor is not started reason=--no-vendor");
                         return Ok(service)
                     },
-                    Err(err) => return Err(err),
-                }
+                    (_, Some(relay_key)) => relay_key,
+                    (Some(true), None) => return Err("--vendor requires a --relay-key".to_string().into()),
+                    (None, None) => {
+                        warn!(target: "vendor::supervisor", "the vendor is not started reason=no --relay-key");
+                        return Ok(service)
+                    },
+                };
+                let (relay_account, eth_key) = relay_key::load(
+                    service.keystore(),
+                    Path::new(&keystore_path),
+                    &relay_key,
+                    &relay_password,
+                    relay_signer.as_ref().map(|endpoint| (endpoint.as_str(), relay_signer_auth)),
+                    eth_key_file.as_ref().map(PathBuf::as_path),
+                ).map_err(|e| format!("refusing to start the vendor: {}", e))?;
+                executor.spawn(start_vendor(
+                    vendor_settings.into_config(db_path, eth_key, relay_dry_run),
+                    health,
+                    pending,
+                    metrics,
+                    relay_account,
+                    service.network(),
+                    service.client(),
+                    service.transaction_pool(),
+                    service.on_exit(),
+                ));
+                Ok(service)
             }
 		},
 		AuthoritySetup = {
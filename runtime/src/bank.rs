@@ -9,8 +9,10 @@ use session::*;
 use rstd::prelude::Vec;
 use runtime_primitives::traits::*;
 use srml_support::{StorageValue, StorageMap, dispatch::Result};
-use system::{self, ensure_signed};
+use system::{self, ensure_signed, ensure_inherent};
 use sigcount;
+use matrix;
+use relay_sig;
 //use rstd::cmp::min;
 use rstd;
 /*
@@ -24,7 +26,7 @@ pub struct RewardFactor<U> {
 }
 */
 
-pub trait Trait: balances::Trait + session::Trait + sigcount::Trait{
+pub trait Trait: balances::Trait + session::Trait + sigcount::Trait + matrix::Trait{
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -41,67 +43,43 @@ decl_module! {
         //(origin, message: Vec, signature: Vec)
         pub fn deposit(origin, message: Vec<u8>, signature: Vec<u8>) -> Result {
             let sender = ensure_signed(origin)?;
-            runtime_io::print("====================deposit===============");
-/*
-            //TODO: 在这里判断 sender 是否有权限提交 后期启动节点时写入
-            let validators = <session::Module<T>>::validators();
-            ensure!(validators.contains(&sender),"Not validator");
-*/
-            // 解析message --> 以太坊交易的hash tx_hash  abmatrix上的账号who
-            //                 该账号的抵押数量amount   整个交易的签名signature_hash
-            let (tx_hash, who, amount, signature_hash,_coin_hash) = Self::split_message(message.clone(),signature);
-            // 整个交易的hash
-            //let message_hash = Decode::decode(&mut &message.encode()[..]).unwrap();
-            runtime_io::print("开始判断是否重复抵押");
-            // ensure no repeat desposit
-            ensure!(Self::despositing_account().iter().find(|&t| t == &who).is_none(), "Cannot deposit if already depositing.");
-            // ensure no repeat intentions to desposit
-            ensure!(Self::intentions_desposit_vec().iter().find(|&t| t == &who).is_none(), "Cannot deposit if already in queue.");
+            Self::relay_deposit(sender, message, signature)
+        }
 
-            //check the validity and number of signatures
-            runtime_io::print("开始检查签名");
-            match  Self::check_signature(sender.clone(), tx_hash, signature_hash, tx_hash){
-                Ok(y) =>  runtime_io::print("ok") ,
-                Err(x) => return Err(x),
-            }
-            // update the list of intentions to desposit
-            runtime_io::print("抵押账号通过验证=>存储其 accountid 和 balance 入intentions");
-            // update the list of intentions to desposit
-            <IntentionsDespositVec<T>>::put({
-                let mut v =  Self::intentions_desposit_vec();
-                v.push(who.clone());
-                v
-            });
-            <IntentionsDesposit<T>>::insert(who.clone(),T::Balance::sa(amount as u64));
-            // 发送一个event
-            Self::deposit_event(RawEvent::AddDepositingQueue(who));
+        /// Many deposit messages in one extrinsic, each with its signature, at most
+        /// `matrix::MAX_BATCH_SIZE`. entries are processed independently and a
+        /// `BatchItemAccepted` or `BatchItemRejected` event is deposited for each.
+        pub fn deposit_batch(origin, batch: Vec<(Vec<u8>, Vec<u8>)>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(batch.len() <= matrix::MAX_BATCH_SIZE, "batch too large");
+            Self::relay_all(&sender, batch, Self::relay_deposit);
             Ok(())
         }
 
         /// 追加
         pub fn more_deposit(origin, message: Vec<u8>, signature: Vec<u8>) -> Result{
              let sender = ensure_signed(origin)?;
-             runtime_io::print("===============more_deposit===============");
+             runtime_trace!("runtime::bank", "more_deposit: message received");
 
                 // 解析message --> 以太坊交易的hash tx_hash  abmatrix上的账号who
             //                 该账号的抵押数量amount   整个交易的签名signature_hash
             let (tx_hash, who, amount, signature_hash,_coin_hash) = Self::split_message(message.clone(),signature);
             // 整个交易的hash
             //let message_hash = Decode::decode(&mut &message.encode()[..]).unwrap();
-            runtime_io::print("开始判断是否重复抵押");
+            runtime_trace!("runtime::bank", "deposit: checking for a repeated deposit");
             // ensure no repeat desposit
             ensure!(!Self::despositing_account().iter().find(|&t| t == &who).is_none(), "Cannot more deposit if not depositing.");
             // ensure no repeat intentions to desposit
             ensure!(Self::intentions_desposit_vec().iter().find(|&t| t == &who).is_none(), "Cannot deposit if already in queue.");
 
             //check the validity and number of signatures
-            runtime_io::print("开始检查签名");
+            runtime_trace!("runtime::bank", "checking signatures");
             match  Self::check_signature(sender.clone(), tx_hash, signature_hash, tx_hash){
-                Ok(y) =>  runtime_io::print("ok") ,
+                Ok(_) => runtime_trace!("runtime::bank", "relay signature accepted"),
                 Err(x) => return Err(x),
             }
             // update the list of intentions to desposit
-            runtime_io::print("more 抵押账号通过验证=>存储其 accountid 和 balance 入intentions");
+            runtime_trace!("runtime::bank", "more_deposit: queued");
             // update the list of intentions to desposit
             <IntentionsDespositVec<T>>::put({
                 let mut v =  Self::intentions_desposit_vec();
@@ -133,13 +111,13 @@ decl_module! {
             //decode the signature
             let signature_hash =  Decode::decode(&mut &signature.encode()[..]).unwrap();
 
-            runtime_io::print("开始检查签名");
+            runtime_trace!("runtime::bank", "checking signatures");
             match  Self::check_signature(sender.clone(), hash, signature_hash, hash){
-                Ok(y) =>  runtime_io::print("ok") ,
+                Ok(_) => runtime_trace!("runtime::bank", "relay signature accepted"),
                 Err(x) => return Err(x),
             }
 
-            runtime_io::print("抵押账号通过验证=>存储其 accountid 和 balance 入intentions");
+            runtime_trace!("runtime::bank", "deposit: queued");
             // update the list of intentions to desposit
             <IntentionsDespositVec<T>>::put({
                 let mut v =  Self::intentions_desposit_vec();
@@ -190,31 +168,21 @@ decl_module! {
         /// origin, message: Vec<u8>, signature: Vec<u8>
          pub fn withdraw(origin, message: Vec<u8>, signature: Vec<u8>) -> Result {
             let sender = ensure_signed(origin)?;
+            Self::relay_withdraw(sender, message, signature)
+        }
 
-            let validators = <session::Module<T>>::validators();
-            ensure!(validators.contains(&sender),"Not validator");
-            // 解析message --> hash  tag  id  amount
-            let (_tx_hash,who,_amount,signature_hash,_coin_hash) = Self::split_message(message.clone(),signature);
-            let message_hash = Decode::decode(&mut &message.encode()[..]).unwrap();
-
-            //check the validity and number of signatures
-            runtime_io::print("开始检查签名");
-            match  Self::check_signature(sender.clone(), message_hash, signature_hash, message_hash){
-                Ok(y) =>  runtime_io::print("ok") ,
-                Err(x) => return Err(x),
-            }
-            // ensure no repeat
-            ensure!(!Self::despositing_account().iter().find(|&t| t == &who).is_none(), "Cannot deposit if not depositing.");
-            ensure!(Self::intentions_withdraw().iter().find(|&t| t == &who).is_none(), "Cannot withdraw2 if already in withdraw2 queue.");
-            runtime_io::print("============withdraw2===========");
-            <IntentionsWithdraw<T>>::put({
-                let mut v =  Self::intentions_withdraw();
-                v.push(who.clone());
-                v
-            });
-
-            // 发送一个event
-            Self::deposit_event(RawEvent::AddWithdrawQueue(who));
+        /// Deposit or withdraw messages attested by a validator, unsigned and free of
+        /// fees and nonces. see `matrix::Module::validate_attestation`.
+        fn attest(origin, attestation: matrix::Attestation<T::SessionKey>, proof: Vec<u8>) -> Result {
+            ensure_inherent(origin)?;
+            let sender = <matrix::Module<T>>::check_attestation(&attestation, &proof)?;
+            let relay: fn(T::AccountId, Vec<u8>, Vec<u8>) -> Result = match attestation.kind {
+                relay_sig::KIND_DEPOSIT => Self::relay_deposit,
+                relay_sig::KIND_WITHDRAW => Self::relay_withdraw,
+                _ => return Err("not a bank message kind"),
+            };
+            <matrix::Module<T>>::note_attested(&sender, &attestation.messages);
+            Self::relay_all(&sender, attestation.messages, relay);
             Ok(())
         }
 
@@ -228,13 +196,13 @@ decl_module! {
             ensure!(Self::intentions_withdraw().iter().find(|&t| t == &who).is_none(), "Cannot withdraw2 if already in withdraw2 queue.");
 
             let signature_hash =  Decode::decode(&mut &signature.encode()[..]).unwrap();
-            runtime_io::print("开始检查withdraw签名");
+            runtime_trace!("runtime::bank", "withdraw2: checking signatures");
             match  Self::check_signature(sender.clone(), hash, signature_hash, hash){
-                Ok(y) =>  runtime_io::print("ok") ,
+                Ok(_) => runtime_trace!("runtime::bank", "relay signature accepted"),
                 Err(x) => return Err(x),
             }
 
-            runtime_io::print("============withdraw2===========");
+            runtime_trace!("runtime::bank", "withdraw2: queued");
             <IntentionsWithdraw<T>>::put({
                 let mut v =  Self::intentions_withdraw();
                 v.push(who.clone());
@@ -374,12 +342,104 @@ decl_event! {
 		AddWithdrawQueue(AccountId),
         /// a new seesion start
         NewRewardSession(BlockNumber),
+        /// entry of a batch call with the hash of its message accepted.
+        BatchItemAccepted(u32, Hash),
+        /// entry of a batch call with the hash of its message rejected, with the reason.
+        BatchItemRejected(u32, Hash, Vec<u8>),
 
     }
 }
 
 impl<T: Trait> Module<T>
 {
+    /// Process one deposit message relayed by `sender`.
+    fn relay_deposit(sender: T::AccountId, message: Vec<u8>, signature: Vec<u8>) -> Result {
+        runtime_trace!("runtime::bank", "deposit: message received");
+        <matrix::Module<T>>::check_relay_signature(&sender, relay_sig::KIND_DEPOSIT, &message, &signature)?;
+/*
+        //TODO: 在这里判断 sender 是否有权限提交 后期启动节点时写入
+        let validators = <session::Module<T>>::validators();
+        ensure!(validators.contains(&sender),"Not validator");
+*/
+        // 解析message --> 以太坊交易的hash tx_hash  abmatrix上的账号who
+        //                 该账号的抵押数量amount   整个交易的签名signature_hash
+        let (tx_hash, who, amount, signature_hash,_coin_hash) = Self::split_message(message.clone(),signature);
+        // 整个交易的hash
+        //let message_hash = Decode::decode(&mut &message.encode()[..]).unwrap();
+        runtime_trace!("runtime::bank", "deposit: checking for a repeated deposit");
+        // ensure no repeat desposit
+        ensure!(Self::despositing_account().iter().find(|&t| t == &who).is_none(), "Cannot deposit if already depositing.");
+        // ensure no repeat intentions to desposit
+        ensure!(Self::intentions_desposit_vec().iter().find(|&t| t == &who).is_none(), "Cannot deposit if already in queue.");
+
+        //check the validity and number of signatures
+        runtime_trace!("runtime::bank", "checking signatures");
+        let checked = Self::check_signature(sender.clone(), tx_hash, signature_hash, tx_hash);
+        Self::note_transfer(relay_sig::KIND_DEPOSIT, &message, tx_hash);
+        match checked {
+            Ok(_) => runtime_trace!("runtime::bank", "relay signature accepted"),
+            Err(x) => return Err(x),
+        }
+        // update the list of intentions to desposit
+        runtime_trace!("runtime::bank", "deposit: queued");
+        // update the list of intentions to desposit
+        <IntentionsDespositVec<T>>::put({
+            let mut v =  Self::intentions_desposit_vec();
+            v.push(who.clone());
+            v
+        });
+        <IntentionsDesposit<T>>::insert(who.clone(),T::Balance::sa(amount as u64));
+        // 发送一个event
+        Self::deposit_event(RawEvent::AddDepositingQueue(who));
+        Ok(())
+    }
+
+    /// Process one withdraw message relayed by `sender`.
+    fn relay_withdraw(sender: T::AccountId, message: Vec<u8>, signature: Vec<u8>) -> Result {
+        let validators = <session::Module<T>>::validators();
+        ensure!(validators.contains(&sender),"Not validator");
+        <matrix::Module<T>>::check_relay_signature(&sender, relay_sig::KIND_WITHDRAW, &message, &signature)?;
+        // 解析message --> hash  tag  id  amount
+        let (_tx_hash,who,_amount,signature_hash,_coin_hash) = Self::split_message(message.clone(),signature);
+        let message_hash = Decode::decode(&mut &message.encode()[..]).unwrap();
+
+        //check the validity and number of signatures
+        runtime_trace!("runtime::bank", "checking signatures");
+        let checked = Self::check_signature(sender.clone(), message_hash, signature_hash, message_hash);
+        Self::note_transfer(relay_sig::KIND_WITHDRAW, &message, message_hash);
+        match checked {
+            Ok(_) => runtime_trace!("runtime::bank", "relay signature accepted"),
+            Err(x) => return Err(x),
+        }
+        // ensure no repeat
+        ensure!(!Self::despositing_account().iter().find(|&t| t == &who).is_none(), "Cannot deposit if not depositing.");
+        ensure!(Self::intentions_withdraw().iter().find(|&t| t == &who).is_none(), "Cannot withdraw2 if already in withdraw2 queue.");
+        runtime_trace!("runtime::bank", "withdraw2: queued");
+        <IntentionsWithdraw<T>>::put({
+            let mut v =  Self::intentions_withdraw();
+            v.push(who.clone());
+            v
+        });
+
+        // 发送一个event
+        Self::deposit_event(RawEvent::AddWithdrawQueue(who));
+        Ok(())
+    }
+
+    /// Process each of `batch` with `relay`, independently, and deposit a
+    /// `BatchItemAccepted` or `BatchItemRejected` event for each.
+    fn relay_all<F>(sender: &T::AccountId, batch: Vec<(Vec<u8>, Vec<u8>)>, relay: F) where
+        F: Fn(T::AccountId, Vec<u8>, Vec<u8>) -> Result
+    {
+        for (index, (message, signature)) in batch.into_iter().enumerate() {
+            let hash = T::Hashing::hash_of(&message);
+            match relay(sender.clone(), message, signature) {
+                Ok(()) => Self::deposit_event(RawEvent::BatchItemAccepted(index as u32, hash)),
+                Err(reason) => Self::deposit_event(RawEvent::BatchItemRejected(index as u32, hash, reason.as_bytes().to_vec())),
+            }
+        }
+    }
+
     fn  split_message( message: Vec<u8>, signature: Vec<u8>) -> (T::Hash,T::AccountId,u64,T::Hash,T::Hash) {
 
         // 解析message --> hash  tag  id  amount
@@ -436,7 +496,7 @@ impl<T: Trait> Module<T>
         let (should_end_session, apply_rewards) = <ForcingNewSession<T>>::take()
             .map_or((is_final_block, is_final_block), |apply_rewards| (true, apply_rewards));
         if should_end_session {
-            runtime_io::print("--------安排上了04-01--------");
+            runtime_trace!("runtime::bank", "session: rotating");
             Self::rotate_session(is_final_block, apply_rewards);
         }
     }
@@ -450,7 +510,7 @@ impl<T: Trait> Module<T>
     /// 把新的 depositingqueue 加入 实际depositing列表   或者 把不存钱的账户从列表里删除
     /// 并且根据其存的金额 之后每个session都对列表里的人存的钱发一定比例到他的balance里
     pub fn rotate_session(is_final_block: bool, _apply_rewards: bool) {
-        runtime_io::print("开始调整depositing列表，同时开始给他们发钱");
+        runtime_trace!("runtime::bank", "session: adjusting deposits and paying rewards");
         let now = <timestamp::Module<T>>::get();
         let _time_elapsed = now.clone() - Self::current_start();
         let session_index = <CurrentIndex<T>>::get() + One::one();
@@ -488,11 +548,11 @@ impl<T: Trait> Module<T>
             let balances = <IntentionsDesposit<T>>::get(who.clone());
             //if Self::more_desposit(&who).is_none() {
             if <MoreDesposit<T>>::get(&who).is_none() {
-                runtime_io::print("========first_desposit===========");
+                runtime_trace!("runtime::bank", "session: first deposit enacted");
                 <DespositingBalance<T>>::insert(who.clone(), balances);
                 <DespositingTime<T>>::insert(who.clone(), 0);
             }else {
-                runtime_io::print("========more_desposit===========");
+                runtime_trace!("runtime::bank", "session: more deposit enacted");
                 let now_desposit_balance = Self::despositing_banance(&who);
                 <DespositingBalance<T>>::insert(who.clone(), balances+now_desposit_balance);
                 <MoreDesposit<T>>::remove(&who);
@@ -510,7 +570,7 @@ impl<T: Trait> Module<T>
         let mut des_vec2 = Self::despositing_account();
         let mut vec_with =  Self::intentions_withdraw();
         while let Some(who) = vec_with.pop() {
-            runtime_io::print("========remove===========");
+            runtime_trace!("runtime::bank", "session: withdraw enacted");
             //增加 despoit 记录  同时创建session记录
             let balances = <DespositingBalance<T>>::get(who.clone());
 
@@ -525,7 +585,7 @@ impl<T: Trait> Module<T>
         <IntentionsWithdraw<T>>::put(vec_with);
         <DespoitingAccount<T>>::put(des_vec2);
         
-        runtime_io::print("对表里的session time进行更新");
+        runtime_trace!("runtime::bank", "session: updating deposit times");
         //对表进行session time更新
         Self::despositing_account().iter().enumerate().for_each(|(_i,v)|{
             <DespositingTime<T>>::insert(v,Self::despositing_time(v)+1);
@@ -547,7 +607,7 @@ impl<T: Trait> Module<T>
         //首先判断session 决定一个 时间比率
         //再判断balance 决定一个 存款比率
         //两个比率结合起来决定一个 乘积因子Xbalance => 然后往账户的记录上记录奖励额度
-        runtime_io::print("发钱====发到记录里面！！！！！！！！！！！！");
+        runtime_trace!("runtime::bank", "session: recording rewards");
         Self::despositing_account().iter().enumerate().for_each(|(_i,v)|{
             let reward = Self::reward_set(v.clone(),<DespositingTime<T>>::get(v),<DespositingBalance<T>>::get(v));
             let now_reward = <RewardRecord<T>>::get(v);
@@ -561,11 +621,8 @@ impl<T: Trait> Module<T>
         //首先判断session 决定一个 时间比率
         //再判断balance 决定一个 存款比率
         //两个比率结合起来决定一个 乘积因子Xbalance => 然后往账户发
-        runtime_io::print("发钱发钱发钱发钱发钱发钱发钱发钱发钱发钱");
+        runtime_trace!("runtime::bank", "session: paying rewards");
         Self::despositing_account().iter().enumerate().for_each(|(_i,v)|{
-            //TODO:测试时候注释
-            runtime_io::print("================TEST==================");
-
             let reward = Self::reward_set(v.clone(),<DespositingTime<T>>::get(v),<DespositingBalance<T>>::get(v));
             let _ = <balances::Module<T>>::reward(v, reward);
 
@@ -621,12 +678,19 @@ impl<T: Trait> Module<T>
         <sigcount::Module<T>>::check_signature(who,tx,signature ,message_hash)
     }
 
+    /// Index `message` for `transfer_status` once a signature of it was counted under `key`,
+    /// short of the threshold or not.
+    fn note_transfer(kind: u8, message: &[u8], key: T::Hash) {
+        if <sigcount::Module<T>>::num_of_signed(key) > 0 {
+            <matrix::Module<T>>::note_transfer(kind, message, key);
+        }
+    }
+
     pub fn check_secp512(signature: &[u8; 65], tx: &[u8; 32]) -> Result {
-        runtime_io::print("asd");
-        //TODO: if runtime_io::secp256k1_ecdsa_recover(signature,tx).is_ok(){ } else {
-        //  return Err(()); }
-        //TODO:
-        Ok(())
+        match relay_sig::recover(&signature[..], tx) {
+            Some(_) => Ok(()),
+            None => Err("invalid secp256k1 signature"),
+        }
     }
 
 
@@ -41,10 +41,22 @@ extern crate sr_version as version;
 extern crate node_primitives;
 extern crate substrate_consensus_aura_primitives as consensus_aura;
 
+/// Print `$message` under `$target` to the node log, only built with the `debug` feature.
+/// The calling module needs `runtime_io` in scope.
+macro_rules! runtime_trace {
+	($target:expr, $message:expr) => {{
+		#[cfg(feature = "debug")]
+		runtime_io::print(concat!($target, ": ", $message));
+	}}
+}
+
 pub mod matrix;
 pub mod sigcount;
 pub mod bank;
 pub mod votereward;
+pub mod relay_sig;
+#[cfg(test)]
+mod mock;
 
 use rstd::prelude::*;
 use substrate_primitives::u32_trait::{_2, _4};
@@ -82,15 +94,15 @@ pub use bank::Call as BankCall;
 pub use votereward::Call as VoteCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use srml_support::{StorageValue, RuntimeMetadata};
-pub use system::EventRecord;
+pub use system::{EventRecord, Phase, Event as SystemEvent};
 
 /// Runtime version.
 pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node"),
 	impl_name: create_runtime_str!("substrate-node"),
 	authoring_version: 10,
-	spec_version: 16,
-	impl_version: 16,
+	spec_version: 17,
+	impl_version: 17,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -253,7 +265,7 @@ construct_runtime!(
 		Treasury: treasury,
 		Contract: contract::{Module, Call, Config<T>, Event<T>},
 		Sudo: sudo,
-        Matrix: matrix::{Module, Call, Storage, Event<T>},
+        Matrix: matrix::{Module, Call, Storage, Event<T>, Config<T>},
         Sigcount: sigcount::{Module, Call, Storage,Event<T>},
         Bank: bank::{Module, Call, Storage, Event<T>,Config<T>},
         Vote: votereward::{Module, Call, Storage, Event<T>},
@@ -281,6 +293,30 @@ decl_runtime_apis! {
 	pub trait VendorApi {
 		fn account_nonce(account: AccountId) -> Index;
 		fn authorities() -> Vec<SessionKey>;
+		/// Whether `account` attested the message of hash `message`.
+		fn attested(account: AccountId, message: Hash) -> bool;
+		/// Whether `account` sent no relay heartbeat in the current session yet.
+		fn heartbeat_due(account: AccountId) -> bool;
+		/// Index of the current session.
+		fn session_index() -> BlockNumber;
+	}
+
+	pub trait BridgeApi {
+		/// `relay_sig` kind, signatures and whether the threshold was reached of the
+		/// message relayed from the Ethereum transaction `tx_hash`.
+		fn transfer_status(tx_hash: Hash) -> Option<(u8, u64, bool)>;
+		/// Deposited balance, balance waiting to be deposited and sessions deposited of `account`.
+		fn deposit_info(account: AccountId) -> (Balance, Balance, u32);
+		/// Bank rewards recorded and staking rewards paid of `account`.
+		fn rewards_of(account: AccountId) -> (Balance, Balance);
+		/// Balance deposited in the bank by every account.
+		fn total_deposits() -> Balance;
+	}
+
+	pub trait ParticipationApi {
+		/// Last sessions of `account` as validator: (session index, relay messages
+		/// it co-signed, relay messages that reached the signature threshold).
+		fn participation(account: AccountId) -> Vec<(BlockNumber, u32, u32)>;
 	}
 }
 
@@ -333,6 +369,18 @@ impl_runtime_apis! {
 
 	impl client_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
+			// relay attestations and heartbeats are unsigned, authenticated by the session key of an authority.
+			if tx.signature.is_none() {
+				match tx.function {
+					Call::Matrix(MatrixCall::attest(ref attestation, ref proof)) =>
+						return Matrix::validate_attestation(attestation, proof, &[relay_sig::KIND_INGRESS, relay_sig::KIND_EGRESS]),
+					Call::Bank(BankCall::attest(ref attestation, ref proof)) =>
+						return Matrix::validate_attestation(attestation, proof, &[relay_sig::KIND_DEPOSIT, relay_sig::KIND_WITHDRAW]),
+					Call::Matrix(MatrixCall::heartbeat(ref heartbeat, ref proof)) =>
+						return Matrix::validate_heartbeat(heartbeat, proof),
+					_ => (),
+				}
+			}
 			Executive::validate_transaction(tx)
 		}
 	}
@@ -371,5 +419,41 @@ impl_runtime_apis! {
 		fn authorities() -> Vec<SessionKey> {
 			Consensus::authorities()
 		}
+
+		fn attested(account: AccountId, message: Hash) -> bool {
+			Matrix::attested((account, message))
+		}
+
+		fn heartbeat_due(account: AccountId) -> bool {
+			Matrix::heartbeat_due(&account)
+		}
+
+		fn session_index() -> BlockNumber {
+			Session::current_index()
+		}
+	}
+
+	impl self::BridgeApi<Block> for Runtime {
+		fn transfer_status(tx_hash: Hash) -> Option<(u8, u64, bool)> {
+			Matrix::transfer_status(tx_hash)
+		}
+
+		fn deposit_info(account: AccountId) -> (Balance, Balance, u32) {
+			(Bank::despositing_banance(&account), Bank::intentions_desposit(&account), Bank::despositing_time(&account))
+		}
+
+		fn rewards_of(account: AccountId) -> (Balance, Balance) {
+			(Bank::reward_record(&account), Vote::interest_of(&account))
+		}
+
+		fn total_deposits() -> Balance {
+			Bank::total_despositing_balance()
+		}
+	}
+
+	impl self::ParticipationApi<Block> for Runtime {
+		fn participation(account: AccountId) -> Vec<(BlockNumber, u32, u32)> {
+			Sigcount::participation(account)
+		}
 	}
 }
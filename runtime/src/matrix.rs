@@ -2,17 +2,90 @@ extern crate srml_session as session;
 extern crate srml_balances as balances;
 extern crate sr_io as runtime_io;
 extern crate substrate_primitives as primitives;
+extern crate srml_consensus as consensus;
 
 
 
 use rstd::prelude::Vec;
+use rstd::result;
 use runtime_primitives::traits::*;
+use runtime_primitives::codec::{Decode, Encode};
+use runtime_primitives::transaction_validity::TransactionValidity;
 use srml_support::{ StorageMap, dispatch::Result};
-use { system::{self, ensure_signed}};
+use { system::{self, ensure_signed, ensure_inherent}};
+use relay_sig;
+use sigcount;
+
+
+
+/// Most entries of a batch call. an entry weighs about as much as a single
+/// relay extrinsic, a full batch stays within the weight of a block.
+///
+/// a count and not a weight: dispatchables of this runtime carry no weight, a
+/// block is only bounded by the encoded size of its extrinsics. entries are a
+/// `MESSAGE_LENGTH` message and a 65 bytes signature, so 64 of them are about
+/// 12KB, far below a block, and verifying their signatures stays short.
+pub const MAX_BATCH_SIZE: usize = 64;
+/// Blocks an attestation stays valid in the pool for.
+pub const ATTESTATION_LONGEVITY: u64 = 64;
+/// Pool priority of attestations, above any fee paying extrinsic.
+const ATTESTATION_PRIORITY: u64 = 1 << 20;
+/// Prefix of the pool tags provided by attestations.
+const ATTESTATION_TAG: &[u8] = b"relay-attestation";
+/// Prefix of the payload a validator signs with its session key to reject a relay message.
+pub const REJECTION_TAG: &[u8] = b"relay-rejection";
+/// Prefix of the pool tags provided by heartbeats.
+const HEARTBEAT_TAG: &[u8] = b"relay-heartbeat";
+/// Prefix of the payload a validator signs with its session key to attest relay messages.
+pub const ATTESTATION_PROOF_TAG: &[u8] = b"relay-attestation-proof";
+/// Prefix of the payload a validator signs with its session key for its heartbeat.
+pub const HEARTBEAT_PROOF_TAG: &[u8] = b"relay-heartbeat-proof";
+/// Length of an ingress or egress message: tag, recipient, amount and transaction hash.
+pub const MESSAGE_LENGTH: usize = 116;
+/// Length of a deposit or withdraw message: coin, account, amount and transaction hash.
+pub const BANK_MESSAGE_LENGTH: usize = 128;
+/// Offset of the 32 bytes amount in an ingress or egress message.
+const VALUE_OFFSET: usize = 52;
+
+/// Relay messages of one kind attested by a validator, in an unsigned extrinsic
+/// authenticated by the session key signature of its `attestation_payload`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Attestation<SessionKey> {
+    /// Session key of the attesting validator.
+    pub validator: SessionKey,
+    /// `relay_sig` kind of the messages.
+    pub kind: u8,
+    /// Messages with their relay signature.
+    pub messages: Vec<(Vec<u8>, Vec<u8>)>,
+}
 
+/// Liveness of the relay duties of a validator, the last Ethereum block its
+/// vendor processed on each chain, in an unsigned extrinsic authenticated by
+/// the session key signature of its `heartbeat_payload`. one per validator and session.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Heartbeat<SessionKey, BlockNumber, Hash> {
+    /// Session key of the validator.
+    pub validator: SessionKey,
+    /// Index of the session the heartbeat is for.
+    pub session: BlockNumber,
+    /// Chain tag and last processed block of each chain.
+    pub blocks: Vec<(Hash, u64)>,
+}
 
+/// The payload the session key signs to attest `attestation`, tagged so it can't
+/// be taken for a consensus message or a heartbeat.
+pub fn attestation_payload<SessionKey: Encode>(attestation: &Attestation<SessionKey>) -> Vec<u8> {
+    (ATTESTATION_PROOF_TAG, attestation).encode()
+}
+
+/// The payload the session key signs for `heartbeat`.
+pub fn heartbeat_payload<SessionKey: Encode, BlockNumber: Encode, Hash: Encode>(heartbeat: &Heartbeat<SessionKey, BlockNumber, Hash>) -> Vec<u8> {
+    (HEARTBEAT_PROOF_TAG, heartbeat).encode()
+}
 
-pub trait Trait: balances::Trait + session::Trait{
+pub trait Trait: balances::Trait + session::Trait + sigcount::Trait{
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -28,30 +101,72 @@ decl_module! {
         //  offset 84: 32 bytes :: bytes32 - transaction hash
         pub fn ingress(origin, message: Vec<u8>, signature: Vec<u8>) -> Result {
             let sender = ensure_signed(origin)?;
-            let hash = T::Hashing::hash_of(&message);
+            Self::relay_ingress(sender, message, signature)
+        }
 
-            let signature_hash = T::Hashing::hash_of(&signature);
-            if let Ok(()) = Self::verify_ingress_message(sender,hash,signature_hash ) {
+        /// Many ingress messages in one extrinsic, each with its signature, at most
+        /// `MAX_BATCH_SIZE`. entries are processed independently and a
+        /// `BatchItemAccepted` or `BatchItemRejected` event is deposited for each.
+        pub fn ingress_batch(origin, batch: Vec<(Vec<u8>, Vec<u8>)>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(batch.len() <= MAX_BATCH_SIZE, "batch too large");
+            Self::relay_all(&sender, batch, Self::relay_ingress);
+            Ok(())
+        }
 
-                Self::deposit_event(RawEvent::Ingress(signature.clone(), message.clone()));
-                <IngressOf<T>>::insert(hash, message.clone());
-                return  Ok(());
+        /// Ingress or egress messages attested by a validator, unsigned and free of
+        /// fees and nonces. see `validate_attestation` for what the pool accepts.
+        fn attest(origin, attestation: Attestation<T::SessionKey>, proof: Vec<u8>) -> Result {
+            ensure_inherent(origin)?;
+            let sender = Self::check_attestation(&attestation, &proof)?;
+            let relay: fn(T::AccountId, Vec<u8>, Vec<u8>) -> Result = match attestation.kind {
+                relay_sig::KIND_INGRESS => Self::relay_ingress,
+                relay_sig::KIND_EGRESS => Self::relay_egress,
+                _ => return Err("not a matrix message kind"),
+            };
+            Self::note_attested(&sender, &attestation.messages);
+            Self::relay_all(&sender, attestation.messages, relay);
+            Ok(())
+        }
+
+        /// Heartbeat of a validator's vendor, unsigned and free of fees and nonces.
+        /// see `validate_heartbeat` for what the pool accepts.
+        fn heartbeat(origin, heartbeat: Heartbeat<T::SessionKey, T::BlockNumber, T::Hash>, proof: Vec<u8>) -> Result {
+            ensure_inherent(origin)?;
+            let sender = Self::check_heartbeat(&heartbeat, &proof)?;
+            ensure!(Self::heartbeat_due(&sender), "heartbeat already received this session");
+            <LastHeartbeat<T>>::insert(&sender, heartbeat.session);
+            for (tag, block) in heartbeat.blocks {
+                <ProcessedBlock<T>>::insert((sender.clone(), tag), block);
+            }
+            if Self::offline().contains(&sender) {
+                <Offline<T>>::mutate(|offline| offline.retain(|v| v != &sender));
+                Self::update_signer_count();
+                Self::deposit_event(RawEvent::ValidatorOnline(sender));
+            }
+            Ok(())
+        }
+
+        /// Set the number of sessions without heartbeat a validator is offline after.
+        fn set_offline_sessions(sessions: u32) -> Result {
+            ensure!(sessions > 0, "offline after at least one session");
+            <OfflineSessions<T>>::put(sessions);
+            Ok(())
+        }
+
+        /// flag the validators silent for `OfflineSessions` once the session changed
+        fn on_finalise(_n: T::BlockNumber) {
+            let index = <session::Module<T>>::current_index();
+            if index != Self::last_checked_session() {
+                <LastCheckedSession<T>>::put(index);
+                Self::check_liveness(index);
             }
-            Err("ingress err")
         }
 
         /// Data Forwarding Confirmation Message
         pub fn egress(origin, message: Vec<u8>, signature: Vec<u8>) -> Result {
             let sender = ensure_signed(origin)?;
-            let hash = T::Hashing::hash_of(&message);
-            let signature_hash = T::Hashing::hash_of(&signature);
-            if let Ok(()) = Self::verify_egress_message(sender,hash,signature_hash ) {
-
-                Self::deposit_event(RawEvent::Egress(signature.clone(), message.clone()));
-                <EgressOf<T>>::insert(hash, message.clone());
-                 return  Ok(());
-            }
-             Err("egress err")
+            Self::relay_egress(sender, message, signature)
         }
 
         /// Data Forwarding Timeout Return Message
@@ -69,6 +184,26 @@ decl_module! {
             Ok(())
         }
 
+        /// Set the bridge contract (20 bytes address) of the chain `tag`,
+        /// part of the domain relay signatures of that chain are checked in.
+        fn set_bridge_contract(tag: T::Hash, contract: Vec<u8>) -> Result {
+            ensure!(contract.len() == 20, "contract should be a 20 bytes address");
+            <BridgeContract<T>>::insert(tag, contract);
+            Ok(())
+        }
+
+        /// Set the Ethereum address (20 bytes) whose signatures the sender relays.
+        /// the sender must be a validator of the current session, and `proof` the
+        /// signature by that key of its account id, see `relay_sig::key_proof_hash`.
+        pub fn set_relay_key(origin, address: Vec<u8>, proof: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(address.len() == 20, "relay key should be a 20 bytes address");
+            ensure!(<session::Module<T>>::validators().contains(&sender), "not validator");
+            ensure!(relay_sig::verify_key_proof(&address, &sender.encode(), &proof), "invalid relay key proof");
+            <RelayKey<T>>::insert(sender, address);
+            Ok(())
+        }
+
 
 		}
     }
@@ -103,7 +238,35 @@ decl_storage! {
         //
         EgressOf  get(egress_of) : map T::Hash => Vec<u8>;
 
+        /// Bridge contract of each chain tag, see `relay_sig`.
+        BridgeContract get(bridge_contract) build(|config: &GenesisConfig<T>| config.bridge_contracts.clone()): map T::Hash => Vec<u8>;
+        /// Ethereum address signing the relay messages of each relayer.
+        RelayKey get(relay_key) build(|config: &GenesisConfig<T>| config.relay_keys.clone()): map T::AccountId => Vec<u8>;
+        /// Messages attested by each validator, by the hash of the message.
+        Attested get(attested): map (T::AccountId, T::Hash) => bool;
+
+        /// Kind and signature count key of each relayed message, by the Ethereum
+        /// transaction hash in its last 32 bytes.
+        TransferOf get(transfer_of): map T::Hash => Option<(u8, T::Hash)>;
+
+        /// Session of the last heartbeat of each validator.
+        LastHeartbeat get(last_heartbeat): map T::AccountId => T::BlockNumber;
+        /// Last Ethereum block processed by the vendor of each validator, by chain tag.
+        ProcessedBlock get(processed_block): map (T::AccountId, T::Hash) => u64;
+        /// Validators silent for `OfflineSessions`, not counted in the signature threshold.
+        Offline get(offline): Vec<T::AccountId>;
+        /// Sessions without heartbeat a validator is flagged offline after.
+        OfflineSessions get(offline_sessions): u32 = 3;
+        /// The last session the liveness of validators was checked at.
+        LastCheckedSession get(last_checked_session): T::BlockNumber;
 
+
+    }
+    add_extra_genesis {
+        /// Bridge contract of each chain tag.
+        config(bridge_contracts): Vec<(T::Hash, Vec<u8>)>;
+        /// Relay key of each initial validator.
+        config(relay_keys): Vec<(T::AccountId, Vec<u8>)>;
     }
 }
 
@@ -132,11 +295,261 @@ decl_event! {
 
         NewRewardSession(BlockNumber),
 
+        /// entry of a batch call with the hash of its message accepted.
+        BatchItemAccepted(u32, Hash),
+        /// entry of a batch call with the hash of its message rejected, with the reason.
+        BatchItemRejected(u32, Hash, Vec<u8>),
+
+        /// A validator sent no heartbeat for `OfflineSessions` and is flagged offline.
+        ValidatorOffline(AccountId),
+        /// An offline validator sent a heartbeat again.
+        ValidatorOnline(AccountId),
+
     } 
 }
 
 impl<T: Trait> Module<T>
 {
+    /// Process one ingress message relayed by `sender`.
+    fn relay_ingress(sender: T::AccountId, message: Vec<u8>, signature: Vec<u8>) -> Result {
+        Self::check_relay_signature(&sender, relay_sig::KIND_INGRESS, &message, &signature)?;
+        let hash = T::Hashing::hash_of(&message);
+
+        let signature_hash = T::Hashing::hash_of(&signature);
+        if let Ok(()) = Self::verify_ingress_message(sender,hash,signature_hash ) {
+
+            Self::deposit_event(RawEvent::Ingress(signature.clone(), message.clone()));
+            Self::note_transfer(relay_sig::KIND_INGRESS, &message, hash);
+            <IngressOf<T>>::insert(hash, message.clone());
+            return  Ok(());
+        }
+        Err("ingress err")
+    }
+
+    /// Process one egress message relayed by `sender`.
+    fn relay_egress(sender: T::AccountId, message: Vec<u8>, signature: Vec<u8>) -> Result {
+        Self::check_relay_signature(&sender, relay_sig::KIND_EGRESS, &message, &signature)?;
+        let hash = T::Hashing::hash_of(&message);
+        let signature_hash = T::Hashing::hash_of(&signature);
+        if let Ok(()) = Self::verify_egress_message(sender,hash,signature_hash ) {
+
+            Self::deposit_event(RawEvent::Egress(signature.clone(), message.clone()));
+            Self::note_transfer(relay_sig::KIND_EGRESS, &message, hash);
+            <EgressOf<T>>::insert(hash, message.clone());
+             return  Ok(());
+        }
+         Err("egress err")
+    }
+
+    /// Process each of `batch` with `relay`, independently, and deposit a
+    /// `BatchItemAccepted` or `BatchItemRejected` event for each.
+    fn relay_all<F>(sender: &T::AccountId, batch: Vec<(Vec<u8>, Vec<u8>)>, relay: F) where
+        F: Fn(T::AccountId, Vec<u8>, Vec<u8>) -> Result
+    {
+        for (index, (message, signature)) in batch.into_iter().enumerate() {
+            let hash = T::Hashing::hash_of(&message);
+            match relay(sender.clone(), message, signature) {
+                Ok(()) => Self::deposit_event(RawEvent::BatchItemAccepted(index as u32, hash)),
+                Err(reason) => Self::deposit_event(RawEvent::BatchItemRejected(index as u32, hash, reason.as_bytes().to_vec())),
+            }
+        }
+    }
+
+    /// Ensure `proof` is the session key signature of `attestation` by a current
+    /// authority, and return the account of that authority.
+    pub fn check_attestation(attestation: &Attestation<T::SessionKey>, proof: &[u8]) -> result::Result<T::AccountId, &'static str> {
+        ensure!(!attestation.messages.is_empty(), "empty attestation");
+        ensure!(attestation.messages.len() <= MAX_BATCH_SIZE, "batch too large");
+        let length = Self::message_length(attestation.kind).ok_or("not an attestable message kind")?;
+        ensure!(attestation.messages.iter().all(|(message, _)| message.len() == length), "invalid message length");
+        ensure!(<consensus::Module<T>>::authorities().contains(&attestation.validator), "not an authority");
+        ensure!(Self::verify_session_signature(&attestation.validator, &attestation_payload(attestation), proof), "invalid attestation proof");
+        let key = attestation.validator.encode();
+        Decode::decode(&mut &key[..]).ok_or("authority without account")
+    }
+
+    /// Ensure more than two thirds of the current authorities rejected the message
+    /// of hash `message`, each signing `(REJECTION_TAG, kind, message)` with its session key.
+    pub fn check_rejection(kind: u8, message: T::Hash, rejections: &[(T::SessionKey, Vec<u8>)]) -> Result {
+        let authorities = <consensus::Module<T>>::authorities();
+        let payload = (REJECTION_TAG, kind, message).encode();
+        let mut signers: Vec<&T::SessionKey> = Vec::with_capacity(rejections.len());
+        for (key, proof) in rejections {
+            ensure!(authorities.contains(key), "rejection of a non authority");
+            ensure!(!signers.contains(&key), "duplicate rejection");
+            ensure!(Self::verify_session_signature(key, &payload, proof), "invalid rejection proof");
+            signers.push(key);
+        }
+        ensure!(signers.len() * 3 > authorities.len() * 2, "message rejected without a quorum");
+        Ok(())
+    }
+
+    /// Length of the attestable messages of `kind`.
+    fn message_length(kind: u8) -> Option<usize> {
+        match kind {
+            relay_sig::KIND_INGRESS | relay_sig::KIND_EGRESS => Some(MESSAGE_LENGTH),
+            relay_sig::KIND_DEPOSIT | relay_sig::KIND_WITHDRAW => Some(BANK_MESSAGE_LENGTH),
+            _ => None,
+        }
+    }
+
+    /// Whether two relay messages are different versions of the same Ethereum log:
+    /// equal but for the amount. a transaction may emit several logs of one chain,
+    /// messages differing in anything else are different logs.
+    pub fn conflicting(first: &[u8], second: &[u8]) -> bool {
+        if first == second || first.len() != MESSAGE_LENGTH || second.len() != MESSAGE_LENGTH {
+            return false;
+        }
+        first[..VALUE_OFFSET] == second[..VALUE_OFFSET]
+            && first[VALUE_OFFSET + 32..] == second[VALUE_OFFSET + 32..]
+    }
+
+    fn verify_session_signature(key: &T::SessionKey, payload: &[u8], proof: &[u8]) -> bool {
+        if proof.len() != 64 {
+            return false;
+        }
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(proof);
+        runtime_io::ed25519_verify(&signature, payload, &key.encode()[..])
+    }
+
+    /// Record that `sender` attested `messages`, the pool drops later attestations of them.
+    pub fn note_attested(sender: &T::AccountId, messages: &[(Vec<u8>, Vec<u8>)]) {
+        for (message, _) in messages {
+            <Attested<T>>::insert((sender.clone(), T::Hashing::hash_of(message)), true);
+        }
+    }
+
+    /// Pool validity of an unsigned `attest` call of a module relaying `kinds`: only
+    /// from current authorities, with every message signed by the relay key of the
+    /// authority in the domain of a known chain, providing one tag per (validator,
+    /// message) so duplicates are dropped, and never for a message the validator
+    /// already attested on chain. attestations pay no fees, what would fail at
+    /// dispatch never enters a block.
+    pub fn validate_attestation(attestation: &Attestation<T::SessionKey>, proof: &[u8], kinds: &[u8]) -> TransactionValidity {
+        if !kinds.contains(&attestation.kind) {
+            return TransactionValidity::Invalid;
+        }
+        let sender = match Self::check_attestation(attestation, proof) {
+            Ok(sender) => sender,
+            Err(_) => return TransactionValidity::Invalid,
+        };
+        let mut provides = Vec::with_capacity(attestation.messages.len());
+        for (message, signature) in &attestation.messages {
+            let hash = T::Hashing::hash_of(message);
+            if Self::attested((sender.clone(), hash))
+                || Self::check_relay_signature(&sender, attestation.kind, message, signature).is_err() {
+                return TransactionValidity::Invalid;
+            }
+            provides.push((ATTESTATION_TAG, &attestation.validator, hash).encode());
+        }
+        TransactionValidity::Valid {
+            priority: ATTESTATION_PRIORITY,
+            requires: Vec::new(),
+            provides,
+            longevity: ATTESTATION_LONGEVITY,
+        }
+    }
+
+    /// Ensure `proof` is the session key signature of `heartbeat` by a current
+    /// authority for the current session, and return the account of that authority.
+    pub fn check_heartbeat(heartbeat: &Heartbeat<T::SessionKey, T::BlockNumber, T::Hash>, proof: &[u8]) -> result::Result<T::AccountId, &'static str> {
+        ensure!(heartbeat.session == <session::Module<T>>::current_index(), "heartbeat of another session");
+        ensure!(<consensus::Module<T>>::authorities().contains(&heartbeat.validator), "not an authority");
+        ensure!(Self::verify_session_signature(&heartbeat.validator, &heartbeat_payload(heartbeat), proof), "invalid heartbeat proof");
+        let key = heartbeat.validator.encode();
+        Decode::decode(&mut &key[..]).ok_or("authority without account")
+    }
+
+    /// Whether `who` sent no heartbeat in the current session yet.
+    pub fn heartbeat_due(who: &T::AccountId) -> bool {
+        !<LastHeartbeat<T>>::exists(who) || Self::last_heartbeat(who) != <session::Module<T>>::current_index()
+    }
+
+    /// Pool validity of an unsigned `heartbeat` call: only from current authorities,
+    /// one per validator and session.
+    pub fn validate_heartbeat(heartbeat: &Heartbeat<T::SessionKey, T::BlockNumber, T::Hash>, proof: &[u8]) -> TransactionValidity {
+        match Self::check_heartbeat(heartbeat, proof) {
+            Ok(ref sender) if Self::heartbeat_due(sender) => (),
+            _ => return TransactionValidity::Invalid,
+        }
+        let mut provides = Vec::with_capacity(1);
+        provides.push((HEARTBEAT_TAG, &heartbeat.validator, heartbeat.session).encode());
+        TransactionValidity::Valid {
+            priority: ATTESTATION_PRIORITY,
+            requires: Vec::new(),
+            provides,
+            longevity: ATTESTATION_LONGEVITY,
+        }
+    }
+
+    /// Session validators not flagged offline.
+    pub fn active_validators() -> Vec<T::AccountId> {
+        let offline = Self::offline();
+        <session::Module<T>>::validators().into_iter().filter(|v| !offline.contains(v)).collect()
+    }
+
+    /// Flag the session validators whose last heartbeat is `OfflineSessions` before
+    /// session `index`, and forget the flags of accounts no longer validating.
+    fn check_liveness(index: T::BlockNumber) {
+        let validators = <session::Module<T>>::validators();
+        let silence = T::BlockNumber::sa(Self::offline_sessions() as u64);
+        let mut offline = Self::offline();
+        offline.retain(|v| validators.contains(v));
+        for validator in validators {
+            if offline.contains(&validator) || Self::last_heartbeat(&validator) + silence > index {
+                continue;
+            }
+            Self::deposit_event(RawEvent::ValidatorOffline(validator.clone()));
+            offline.push(validator);
+        }
+        <Offline<T>>::put(offline);
+        Self::update_signer_count();
+    }
+
+    /// Count only the live validators in the signature threshold of `sigcount`.
+    fn update_signer_count() {
+        <sigcount::Module<T>>::set_signer_count(Self::active_validators().len() as u64);
+    }
+
+    /// Index `message` of `kind`, whose signatures are counted under `key`, by the
+    /// Ethereum transaction hash in its last 32 bytes. only called for accepted
+    /// signatures, the first message indexed for a transaction stays.
+    pub fn note_transfer(kind: u8, message: &[u8], key: T::Hash) {
+        if message.len() < 32 {
+            return;
+        }
+        if let Some(tx_hash) = Decode::decode(&mut &message[message.len() - 32..]) {
+            if !<TransferOf<T>>::exists(tx_hash) {
+                <TransferOf<T>>::insert(tx_hash, (kind, key));
+            }
+        }
+    }
+
+    /// Kind, number of signatures and whether the threshold was reached of the
+    /// message relayed from the Ethereum transaction `tx_hash`.
+    pub fn transfer_status(tx_hash: T::Hash) -> Option<(u8, u64, bool)> {
+        let (kind, key) = Self::transfer_of(tx_hash)?;
+        Some(match kind {
+            relay_sig::KIND_INGRESS => (kind, Self::ingress_signed_sender(key).len() as u64, Self::already_sent_ingress(key) == 1),
+            relay_sig::KIND_EGRESS => (kind, Self::egress_signed_sender(key).len() as u64, Self::already_sent_egress(key) == 1),
+            _ => (kind, <sigcount::Module<T>>::num_of_signed(key), <sigcount::Module<T>>::already_sent(key) == 1),
+        })
+    }
+
+    /// Ensure `signature` of `message` was made by the relay key of `sender`
+    /// in the domain of the chain tagged in the first 32 bytes of `message`.
+    pub fn check_relay_signature(sender: &T::AccountId, kind: u8, message: &[u8], signature: &[u8]) -> Result {
+        ensure!(message.len() >= 32, "message without chain tag");
+        let tag: T::Hash = Decode::decode(&mut &message[0..32]).ok_or("invalid chain tag")?;
+        ensure!(<BridgeContract<T>>::exists(tag), "unknown chain tag");
+        ensure!(<RelayKey<T>>::exists(sender), "no relay key");
+        let contract = Self::bridge_contract(tag);
+        let signer = Self::relay_key(sender);
+        ensure!(relay_sig::verify(&signer, &contract, kind, message, signature), "invalid relay signature");
+        Ok(())
+    }
+
 
     /// 数据转发请求消息 ingress
     /// Data Forwarding Confirmation Message
@@ -144,7 +557,7 @@ impl<T: Trait> Module<T>
 
         //是否在验证者集合中
         let validator_set = <session::Module<T>>::validators();
-        ensure!(!validator_set.contains(&sender),"not validator");
+        ensure!(validator_set.contains(&sender),"not validator");
 
         //查看该交易是否存在，没得话添加上去
         if !<NumberOfSignedIngressTx<T>>::exists(message) {
@@ -154,12 +567,7 @@ impl<T: Trait> Module<T>
 
         //查看这个签名的是否重复发送交易 重复发送就
         let mut repeat_vec = Self::ingress_signed_sender(&message);
-        ensure!(repeat_vec.contains(&sender),"repeat!");
-
-        //查看交易是否已被发送
-        if 1 == Self::already_sent_ingress(&message){
-            return Err("has been sent");
-        }
+        ensure!(!repeat_vec.contains(&sender),"repeat!");
 
         //增加一条记录 ->  交易 = vec of 验证者 签名
         let mut stored_vec = Self::ingress_list(&message);
@@ -168,9 +576,18 @@ impl<T: Trait> Module<T>
         //更新重复记录
         repeat_vec.push(sender.clone());
         <IngressSignedSender<T>>::insert(message.clone(),repeat_vec.clone());
+        <sigcount::Module<T>>::note_signed(&sender);
+
+        //查看交易是否已被发送
+        // a signature after the first is kept as participation of the signer, the
+        // message is only relayed once.
+        if 1 == Self::already_sent_ingress(&message){
+            return Err("has been sent");
+        }
 
         //记录已经发送过的交易  同时发送事件Event
         <AlreadySentIngressTx<T>>::insert(&message,1);
+        <sigcount::Module<T>>::note_relayed();
         Self::deposit_event(RawEvent::IngressVerified(message,stored_vec));
         Ok(())
     }
@@ -180,7 +597,7 @@ impl<T: Trait> Module<T>
     fn verify_egress_message(sender: T::AccountId, message: T::Hash, signature: T::Hash) -> Result{
         //是否在验证者集合中
         let validator_set = <session::Module<T>>::validators();
-        ensure!(!validator_set.contains(&sender),"not validator");
+        ensure!(validator_set.contains(&sender),"not validator");
 
         //查看该交易是否存在，没得话添加上去
         if !<NumberOfSignedEgressTx<T>>::exists(message) {
@@ -190,12 +607,7 @@ impl<T: Trait> Module<T>
 
         //查看这个签名的是否重复发送交易 重复发送就滚粗
         let mut repeat_vec = Self::egress_signed_sender(&message);
-        ensure!(repeat_vec.contains(&sender),"repeat!");
-
-        //查看交易是否已被发送
-        if 1 == Self::already_sent_egress(&message){
-            return Err("has been sent");
-        }
+        ensure!(!repeat_vec.contains(&sender),"repeat!");
 
         //增加一条记录 ->  交易 = vec of 验证者 签名
         let mut stored_vec = Self::egress_list(&message);
@@ -204,10 +616,187 @@ impl<T: Trait> Module<T>
         //更新重复记录
         repeat_vec.push(sender.clone());
         <EgressSignedSender<T>>::insert(message.clone(),repeat_vec.clone());
+        <sigcount::Module<T>>::note_signed(&sender);
+
+        //查看交易是否已被发送
+        // a signature after the first is kept as participation of the signer, the
+        // message is only relayed once.
+        if 1 == Self::already_sent_egress(&message){
+            return Err("has been sent");
+        }
 
         //记录已经发送过的交易  同时发送事件Event
         <AlreadySentEgressTx<T>>::insert(&message,1);
+        <sigcount::Module<T>>::note_relayed();
         Self::deposit_event(RawEvent::EgressVerified(message,stored_vec));
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::runtime_io::with_externalities;
+    use node_primitives::{Hash, SessionKey};
+    use mock::{account, new_test_ext, pair, session_key, set_validators};
+    use relay_sig::tests::{unhex, CONTRACT, EIP712_SIGNATURE, INGRESS, SIGNER};
+    use {Matrix, Runtime, Sigcount};
+
+    fn hash(seed: u8) -> Hash {
+        [seed; 32].into()
+    }
+
+    /// An ingress message of chain 1 from transaction `tx` to `recipient`.
+    fn message(tx: u8, recipient: u8, value: u8) -> Vec<u8> {
+        let mut message = vec![1u8; MESSAGE_LENGTH];
+        message[32..VALUE_OFFSET].copy_from_slice(&[recipient; 20]);
+        message[VALUE_OFFSET..VALUE_OFFSET + 32].copy_from_slice(&[value; 32]);
+        message[VALUE_OFFSET + 32..].copy_from_slice(&[tx; 32]);
+        message
+    }
+
+    #[test]
+    fn conflicting_versions_of_one_log() {
+        assert!(Matrix::conflicting(&message(1, 2, 3), &message(1, 2, 4)));
+        assert!(!Matrix::conflicting(&message(1, 2, 3), &message(1, 2, 3)));
+        // other logs of the same transaction, or of another one
+        assert!(!Matrix::conflicting(&message(1, 2, 3), &message(1, 5, 4)));
+        assert!(!Matrix::conflicting(&message(1, 2, 3), &message(6, 2, 4)));
+        assert!(!Matrix::conflicting(&message(1, 2, 3)[..100], &message(1, 2, 4)[..100]));
+    }
+
+    fn valid(validity: TransactionValidity) -> bool {
+        match validity {
+            TransactionValidity::Valid { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The ethers vector of `relay_sig`: its chain with its bridge contract, and
+    /// validator 1 relaying with the key of the vector.
+    fn relay_vector() -> (Vec<u8>, Vec<u8>) {
+        set_validators(&[1, 2]);
+        let message = unhex(INGRESS);
+        <BridgeContract<Runtime>>::insert(Hash::from_slice(&message[0..32]), unhex(CONTRACT));
+        <RelayKey<Runtime>>::insert(account(1), unhex(SIGNER));
+        (message, unhex(EIP712_SIGNATURE))
+    }
+
+    /// `messages` of `kind` attested by validator `seed`, with the proof of its session key.
+    fn attestation(seed: u8, kind: u8, messages: Vec<(Vec<u8>, Vec<u8>)>) -> (Attestation<SessionKey>, Vec<u8>) {
+        let attestation = Attestation { validator: session_key(seed), kind, messages };
+        let proof = pair(seed).sign(&attestation_payload(&attestation)).0.to_vec();
+        (attestation, proof)
+    }
+
+    #[test]
+    fn pool_checks_attested_messages() {
+        with_externalities(&mut new_test_ext(), || {
+            let (message, signature) = relay_vector();
+            let kinds = [relay_sig::KIND_INGRESS, relay_sig::KIND_EGRESS];
+            let (attested, proof) = attestation(1, relay_sig::KIND_INGRESS, vec![(message.clone(), signature.clone())]);
+            assert!(valid(Matrix::validate_attestation(&attested, &proof, &kinds)));
+            // attested through the other module
+            assert!(!valid(Matrix::validate_attestation(&attested, &proof, &[relay_sig::KIND_DEPOSIT, relay_sig::KIND_WITHDRAW])));
+
+            let mut broken = signature.clone();
+            broken[0] ^= 1;
+            let invalid = vec![
+                // signed for another kind
+                attestation(1, relay_sig::KIND_EGRESS, vec![(message.clone(), signature.clone())]),
+                attestation(1, relay_sig::KIND_INGRESS, vec![(message.clone(), signature.clone()), (message.clone(), broken)]),
+                // validator 2 registered no relay key
+                attestation(2, relay_sig::KIND_INGRESS, vec![(message.clone(), signature.clone())]),
+                attestation(1, relay_sig::KIND_INGRESS, vec![(message[..100].to_vec(), signature.clone())]),
+            ];
+            for (attested, proof) in invalid {
+                assert!(!valid(Matrix::validate_attestation(&attested, &proof, &kinds)));
+            }
+        });
+    }
+
+    #[test]
+    fn session_proofs_are_tagged() {
+        with_externalities(&mut new_test_ext(), || {
+            let (message, signature) = relay_vector();
+            let (attested, _) = attestation(1, relay_sig::KIND_INGRESS, vec![(message, signature)]);
+            let untagged = pair(1).sign(&attested.encode()).0.to_vec();
+            assert_eq!(Matrix::check_attestation(&attested, &untagged), Err("invalid attestation proof"));
+
+            let heartbeat = Heartbeat { validator: session_key(1), session: 0, blocks: vec![(hash(1), 10)] };
+            let untagged = pair(1).sign(&heartbeat.encode()).0.to_vec();
+            assert_eq!(Matrix::check_heartbeat(&heartbeat, &untagged), Err("invalid heartbeat proof"));
+            let proof = pair(1).sign(&heartbeat_payload(&heartbeat)).0.to_vec();
+            assert_eq!(Matrix::check_heartbeat(&heartbeat, &proof), Ok(account(1)));
+        });
+    }
+
+    #[test]
+    fn transfer_status_after_relay() {
+        with_externalities(&mut new_test_ext(), || {
+            let (message, signature) = relay_vector();
+            let tx_hash = Hash::from_slice(&message[MESSAGE_LENGTH - 32..]);
+            assert_eq!(Matrix::transfer_status(tx_hash), None);
+            assert_ok!(Matrix::relay_ingress(account(1), message.clone(), signature));
+            assert_eq!(Matrix::transfer_status(tx_hash), Some((relay_sig::KIND_INGRESS, 1, true)));
+            // a late signature of validator 2 is counted.
+            assert_err!(Matrix::verify_ingress_message(account(2), BlakeTwo256::hash_of(&message), hash(20)), "has been sent");
+            assert_eq!(Matrix::transfer_status(tx_hash), Some((relay_sig::KIND_INGRESS, 2, true)));
+        });
+    }
+
+    #[test]
+    fn first_transfer_stays() {
+        with_externalities(&mut new_test_ext(), || {
+            Matrix::note_transfer(relay_sig::KIND_INGRESS, &message(1, 2, 3), hash(10));
+            Matrix::note_transfer(relay_sig::KIND_EGRESS, &message(1, 2, 4), hash(20));
+            assert_eq!(Matrix::transfer_of(hash(1)), Some((relay_sig::KIND_INGRESS, hash(10))));
+        });
+    }
+
+    #[test]
+    fn ingress_only_from_validators() {
+        with_externalities(&mut new_test_ext(), || {
+            set_validators(&[1, 2]);
+            assert_noop!(Matrix::verify_ingress_message(account(3), hash(1), hash(10)), "not validator");
+            assert_ok!(Matrix::verify_ingress_message(account(1), hash(1), hash(10)));
+            assert_eq!(Matrix::already_sent_ingress(hash(1)), 1);
+            assert_eq!(Matrix::ingress_signed_sender(hash(1)), vec![account(1)]);
+            assert_eq!(Matrix::ingress_list(hash(1)), vec![(account(1), hash(10))]);
+            assert_eq!(Sigcount::session_signed(&account(1)), 1);
+            assert_eq!(Sigcount::session_relayed(), 1);
+        });
+    }
+
+    #[test]
+    fn ingress_relayed_once_signed_by_all() {
+        with_externalities(&mut new_test_ext(), || {
+            set_validators(&[1, 2]);
+            assert_ok!(Matrix::verify_ingress_message(account(1), hash(1), hash(10)));
+            assert_noop!(Matrix::verify_ingress_message(account(1), hash(1), hash(11)), "repeat!");
+            assert_err!(Matrix::verify_ingress_message(account(2), hash(1), hash(20)), "has been sent");
+            assert_noop!(Matrix::verify_ingress_message(account(2), hash(1), hash(21)), "repeat!");
+            assert_eq!(Matrix::ingress_signed_sender(hash(1)), vec![account(1), account(2)]);
+            assert_eq!(Sigcount::session_signed(&account(1)), 1);
+            assert_eq!(Sigcount::session_signed(&account(2)), 1);
+            // relayed once
+            assert_eq!(Sigcount::session_relayed(), 1);
+        });
+    }
+
+    #[test]
+    fn egress_only_from_validators() {
+        with_externalities(&mut new_test_ext(), || {
+            set_validators(&[1, 2]);
+            assert_noop!(Matrix::verify_egress_message(account(3), hash(1), hash(10)), "not validator");
+            assert_ok!(Matrix::verify_egress_message(account(2), hash(1), hash(20)));
+            assert_eq!(Matrix::already_sent_egress(hash(1)), 1);
+            assert_eq!(Matrix::egress_list(hash(1)), vec![(account(2), hash(20))]);
+            assert_noop!(Matrix::verify_egress_message(account(2), hash(1), hash(21)), "repeat!");
+            assert_err!(Matrix::verify_egress_message(account(1), hash(1), hash(10)), "has been sent");
+            assert_eq!(Sigcount::session_signed(&account(1)), 1);
+            assert_eq!(Sigcount::session_signed(&account(2)), 1);
+            assert_eq!(Sigcount::session_relayed(), 1);
+        });
+    }
+}
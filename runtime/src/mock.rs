@@ -0,0 +1,39 @@
+//! Helpers of the relay module tests, run against the whole `Runtime` on empty storage.
+
+extern crate sr_io as runtime_io;
+
+use self::runtime_io::TestExternalities;
+use substrate_primitives::{Blake2Hasher, ed25519::Pair, Ed25519AuthorityId};
+use node_primitives::{AccountId, Balance};
+use {Balances, Session};
+
+/// Storage of an empty chain.
+pub fn new_test_ext() -> TestExternalities<Blake2Hasher> {
+	TestExternalities::new(Default::default())
+}
+
+/// The ed25519 pair of the test account `seed`, its session key is the same key.
+pub fn pair(seed: u8) -> Pair {
+	Pair::from_seed(&[seed; 32])
+}
+
+/// The account of `pair(seed)`.
+pub fn account(seed: u8) -> AccountId {
+	pair(seed).public().0.into()
+}
+
+/// The session key of `pair(seed)`.
+pub fn session_key(seed: u8) -> Ed25519AuthorityId {
+	Ed25519AuthorityId(pair(seed).public().0)
+}
+
+/// Make the accounts of `seeds` the session validators and consensus authorities.
+pub fn set_validators(seeds: &[u8]) {
+	let validators: Vec<AccountId> = seeds.iter().map(|seed| account(*seed)).collect();
+	Session::set_validators(&validators);
+}
+
+/// Give `balance` to the account of `seed`.
+pub fn endow(seed: u8, balance: Balance) {
+	Balances::set_free_balance(&account(seed), balance);
+}
@@ -0,0 +1,153 @@
+//! Verification of relay message signatures, the counterpart of `signer::typed`.
+//!
+//! a signature covers the chain tag (the first 32 bytes of every message), the
+//! bridge contract of that chain and the kind of the message, signed either as
+//! an EIP-191 prefixed message or as EIP-712 structured data.
+
+extern crate sr_io as runtime_io;
+
+use rstd::prelude::Vec;
+
+/// Kinds of relayed messages, part of the signed domain.
+pub const KIND_INGRESS: u8 = 0;
+pub const KIND_EGRESS: u8 = 1;
+pub const KIND_DEPOSIT: u8 = 2;
+pub const KIND_WITHDRAW: u8 = 3;
+pub const KIND_SET_AUTHORITIES: u8 = 4;
+
+const DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,address verifyingContract,bytes32 salt)";
+const DOMAIN_NAME: &[u8] = b"ABMatrix Bridge";
+const DOMAIN_VERSION: &[u8] = b"1";
+const RELAY_TYPE: &[u8] = b"Relay(uint8 kind,bytes message)";
+/// Prefix of the account id a relay key signs to prove it belongs to that account.
+const KEY_PROOF_TAG: &[u8] = b"relay-key";
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    runtime_io::keccak_256(data)
+}
+
+/// `keccak256("\x19Ethereum Signed Message:\n32" ++ keccak256(tag ++ contract ++ kind ++ message))`
+pub fn eip191_hash(tag: &[u8], contract: &[u8], kind: u8, message: &[u8]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(tag.len() + contract.len() + 1 + message.len());
+    data.extend_from_slice(tag);
+    data.extend_from_slice(contract);
+    data.push(kind);
+    data.extend_from_slice(message);
+    personal_hash(&data)
+}
+
+/// `keccak256("\x19Ethereum Signed Message:\n32" ++ keccak256(data))`
+fn personal_hash(data: &[u8]) -> [u8; 32] {
+    let mut prefixed = Vec::with_capacity(60);
+    prefixed.extend_from_slice(b"\x19Ethereum Signed Message:\n32");
+    prefixed.extend_from_slice(&keccak(data));
+    keccak(&prefixed)
+}
+
+/// `keccak256("\x19Ethereum Signed Message:\n32" ++ keccak256("relay-key" ++ account))`,
+/// what a relay key signs to be set as the relay key of `account`.
+pub fn key_proof_hash(account: &[u8]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(KEY_PROOF_TAG.len() + account.len());
+    data.extend_from_slice(KEY_PROOF_TAG);
+    data.extend_from_slice(account);
+    personal_hash(&data)
+}
+
+/// Whether `proof` was made by `signer`, a 20 bytes address, over `account`.
+pub fn verify_key_proof(signer: &[u8], account: &[u8], proof: &[u8]) -> bool {
+    signer.len() == 20 && recover(proof, &key_proof_hash(account)).map_or(false, |address| &address[..] == signer)
+}
+
+/// EIP-712 hash of `Relay(uint8 kind,bytes message)` in the bridge domain
+/// `{name, version, verifyingContract: contract, salt: tag}`.
+pub fn eip712_hash(tag: &[u8], contract: &[u8], kind: u8, message: &[u8]) -> [u8; 32] {
+    let mut domain = Vec::with_capacity(5 * 32);
+    domain.extend_from_slice(&keccak(DOMAIN_TYPE));
+    domain.extend_from_slice(&keccak(DOMAIN_NAME));
+    domain.extend_from_slice(&keccak(DOMAIN_VERSION));
+    domain.extend_from_slice(&[0u8; 12]);
+    domain.extend_from_slice(contract);
+    domain.extend_from_slice(tag);
+
+    let mut relay = Vec::with_capacity(3 * 32);
+    relay.extend_from_slice(&keccak(RELAY_TYPE));
+    relay.extend_from_slice(&[0u8; 31]);
+    relay.push(kind);
+    relay.extend_from_slice(&keccak(message));
+
+    let mut typed = Vec::with_capacity(66);
+    typed.extend_from_slice(&[0x19, 0x01]);
+    typed.extend_from_slice(&keccak(&domain));
+    typed.extend_from_slice(&keccak(&relay));
+    keccak(&typed)
+}
+
+/// The Ethereum address that signed `hash`, `v` may be `0, 1` or `27, 28`.
+pub fn recover(signature: &[u8], hash: &[u8; 32]) -> Option<[u8; 20]> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let mut sig = [0u8; 65];
+    sig.copy_from_slice(signature);
+    if sig[64] >= 27 {
+        sig[64] -= 27;
+    }
+    let pubkey = runtime_io::secp256k1_ecdsa_recover(&sig, hash).ok()?;
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&keccak(&pubkey[..])[12..32]);
+    Some(address)
+}
+
+/// Whether `signature` of `message` in the domain of `contract` and `kind` was made
+/// by `signer`, with either scheme. `contract` and `signer` are 20 bytes addresses.
+pub fn verify(signer: &[u8], contract: &[u8], kind: u8, message: &[u8], signature: &[u8]) -> bool {
+    if message.len() < 32 || contract.len() != 20 || signer.len() != 20 {
+        return false;
+    }
+    let tag = &message[0..32];
+    [eip712_hash(tag, contract, kind, message), eip191_hash(tag, contract, kind, message)]
+        .iter()
+        .any(|hash| recover(signature, hash).map_or(false, |address| &address[..] == signer))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // hashes and signatures of `signer::typed`, produced by ethers-rs 2.0.14 from the
+    // `eth_signTypedData_v4` payload of the bridge domain and by `LocalWallet::sign_message`.
+    pub(crate) const INGRESS: &str = "000000000000000000000000000000000000000000000000000000000000000174241db5f3ebaeecf9506e4ae98818609334160400000000000000000000000000000000000000000000000000000000054c56381045bfe274b88120a6b1e5d01b5ec00ab5d01098346e90e7c7a3c9b8f0181c80";
+    pub(crate) const CONTRACT: &str = "d320e46e782417dc06a1f1a37b6d729d42639e0a";
+    pub(crate) const SIGNER: &str = "59dc5d8803b482ddbf361ebaccbacc413925ab28";
+    pub(crate) const EIP712_SIGNATURE: &str = "d557aba41c67527c0a4df52e6804ec8259fe86a7de8644980388596d13e13f131cf4b07833807bf54d1114c9418f0b104b9f544824def9fbfc6b3fd903435d4b1c";
+    const EIP191_SIGNATURE: &str = "3cd2bcc98f6f63a91522363f2b63e7ab7c6c8e38ec7923b421ba271759ca3a83476f6f8057cf33703052c54b8af260e88a6f9c7a10510987341e4f466a4899971b";
+    const LEGACY_SIGNATURE: &str = "31a4639b8025bcd4b33127560b05dee74a05332322cb55abb43b5df333e3d16133b05df05d41390f84b8f9fe4dff3c938be95d77e4a3ce68efa000c99cc2318d1b";
+
+    pub(crate) fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn hashes_match_ethers() {
+        let message = unhex(INGRESS);
+        let contract = unhex(CONTRACT);
+        assert_eq!(eip712_hash(&message[0..32], &contract, KIND_INGRESS, &message).to_vec(),
+            unhex("642e8346328f9eb8599d12e8678f74038d0b757635168dbe0c531f902433b721"));
+        assert_eq!(eip191_hash(&message[0..32], &contract, KIND_INGRESS, &message).to_vec(),
+            unhex("0d59151cc2d31e5562bc5923bc077f8dceea3f39453a0f722f199e38a53719b8"));
+    }
+
+    #[test]
+    fn verifies_typed_signatures_only() {
+        let (message, contract, signer) = (unhex(INGRESS), unhex(CONTRACT), unhex(SIGNER));
+        for signature in &[unhex(EIP712_SIGNATURE), unhex(EIP191_SIGNATURE)] {
+            assert!(verify(&signer, &contract, KIND_INGRESS, &message, signature));
+            assert!(!verify(&signer, &contract, KIND_EGRESS, &message, signature));
+            let mut recovery_id = signature.clone();
+            recovery_id[64] -= 27;
+            assert!(verify(&signer, &contract, KIND_INGRESS, &message, &recovery_id));
+        }
+        // the release signature is bound to no domain and never relays a message.
+        assert!(!verify(&signer, &contract, KIND_INGRESS, &message, &unhex(LEGACY_SIGNATURE)));
+    }
+}
@@ -1,8 +1,12 @@
 extern crate sr_io as runtime_io;
 use rstd::prelude::Vec;
-use {balances, system::{self, ensure_signed}};
+use rstd::cmp;
+use {balances, session, system::{self, ensure_signed}};
 use srml_support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
 
+/// Sessions of participation kept for each validator.
+pub const PARTICIPATION_HISTORY: usize = 28;
+
 
 pub trait Trait: balances::Trait + session::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
@@ -46,6 +50,18 @@ decl_storage! {
         AlreadySentTx get(already_sent) : map T::Hash => u64;
 
         txsave get(tx_save) : Vec<T::Hash>;
+
+        /// Relay messages each validator co-signed in the current session.
+        SessionSigned get(session_signed): map T::AccountId => u32;
+        /// Relay messages that reached the signature threshold in the current session.
+        SessionRelayed get(session_relayed): u32;
+        /// Accounts which co-signed in the current session.
+        SessionSigners get(session_signers): Vec<T::AccountId>;
+        /// Validators able to sign, the signature threshold is never more than two
+        /// thirds of them. not limited if 0.
+        SignerCount get(signer_count): u64;
+        /// Last sessions of each validator: (session index, co-signed, reached threshold).
+        pub Participation get(participation): map T::AccountId => Vec<(T::BlockNumber, u32, u32)>;
         // Nonce: u64;
     }
 }
@@ -138,13 +154,11 @@ impl<T: Trait> Module<T> {
     pub  fn check_signature(who: T::AccountId, transcation: T::Hash, sign: T::Hash, message: T::Hash) -> Result{
         //TODO： 判断这个信息发送的人是否是validator     不在这里 已经前置了
         let sender = who;
-        runtime_io::print("111111");
         <txsave<T>>::put({
           let mut xx = Self::tx_save();
           xx.push(transcation.clone());
           xx  }
         );
-        runtime_io::print("222222");
         //查看该交易是否已经存在，没得话添加上去
         if !<NumberOfSignedContract<T>>::exists(transcation) {
             <NumberOfSignedContract<T>>::insert(&transcation,0);
@@ -160,10 +174,12 @@ impl<T: Trait> Module<T> {
         repeat_vec.push(sign.clone());
         <RepeatPrevent<T>>::insert(transcation.clone(),repeat_vec.clone());
 
-        // 防止一个交易被重复发送，已发送过的会有记录
-        if 1 == Self::already_sent(transcation){
-            return Err("This Transcation already been sent!");
-        }
+        // a validator counts once per transaction, whatever signature it sends
+        let mut stored_vec = Self::record(transcation);
+        ensure!(!stored_vec.iter().any(|(signer, _)| signer == &sender), "This validator already signed!");
+
+        //TODO:其他验证
+        Self::_verify(transcation)?;
 
   /*
         //增加一条记录 ->  交易 验证者 签名
@@ -171,12 +187,9 @@ impl<T: Trait> Module<T> {
    */
 
         //增加一条记录  包含  交易hash => vec (验证者,签名hash)
-        let mut stored_vec = Self::record(transcation);
         stored_vec.push((sender.clone(),sign.clone()));
         <Record<T>>::insert(transcation.clone(),stored_vec.clone());
-
-        //TODO:其他验证
-        Self::_verify(transcation)?;
+        Self::note_signed(&sender);
 
         // 判断签名数量是否达到指定要求
         let numofsigned = Self::num_of_signed(&transcation);
@@ -184,16 +197,122 @@ impl<T: Trait> Module<T> {
             .ok_or("Overflow adding a new sign to Tx")?;
 
         <NumberOfSignedContract<T>>::insert(&transcation,newnumofsigned);
-        if newnumofsigned < Self::min_signature() {
+
+        // 防止一个交易被重复发送，已发送过的会有记录
+        // a signature after the threshold is kept as participation of the signer,
+        // the transaction is only sent once.
+        if 1 == Self::already_sent(transcation){
+            return Err("This Transcation already been sent!");
+        }
+        if newnumofsigned < Self::required_signatures() {
             return Err("Not enough signature!");
         }
 
         // 记录已发送的交易防止重复发送 Record the transaction and sending event
         <AlreadySentTx<T>>::insert(&transcation,1);
+        Self::note_relayed();
 
         // 抛出事件
         Self::deposit_event(RawEvent::Txisok(transcation));
         Self::deposit_event(RawEvent::TranscationVerified(transcation,stored_vec));
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Set the number of validators able to sign, see `required_signatures`.
+    pub fn set_signer_count(count: u64) {
+        <SignerCount<T>>::put(count);
+    }
+
+    /// Signatures a transaction needs: `MinNumOfSignature`, lowered to more than two
+    /// thirds of the signers when fewer of them are live.
+    pub fn required_signatures() -> u64 {
+        match Self::signer_count() {
+            0 => Self::min_signature(),
+            count => cmp::min(Self::min_signature(), count * 2 / 3 + 1),
+        }
+    }
+
+    /// Count a relay message co-signed by `who` in the current session.
+    pub fn note_signed(who: &T::AccountId) {
+        if !<SessionSigned<T>>::exists(who) {
+            <SessionSigners<T>>::mutate(|signers| signers.push(who.clone()));
+        }
+        <SessionSigned<T>>::mutate(who, |signed| *signed = signed.saturating_add(1));
+    }
+
+    /// Count a relay message that reached the signature threshold in the current session.
+    pub fn note_relayed() {
+        <SessionRelayed<T>>::mutate(|relayed| *relayed = relayed.saturating_add(1));
+    }
+
+    /// Close the participation of session `index` for the session validators,
+    /// and start counting the next one.
+    pub fn end_session(index: T::BlockNumber) {
+        let relayed = <SessionRelayed<T>>::take();
+        for validator in <session::Module<T>>::validators() {
+            let signed = Self::session_signed(&validator);
+            <Participation<T>>::mutate(&validator, |history| {
+                if history.len() >= PARTICIPATION_HISTORY {
+                    history.remove(0);
+                }
+                history.push((index, signed, relayed));
+            });
+        }
+        for signer in <SessionSigners<T>>::take() {
+            <SessionSigned<T>>::remove(&signer);
+        }
+    }
+
+    /// Co-signed and relayed messages of the last closed session of `who`, if it validated in it.
+    pub fn last_participation(who: &T::AccountId, index: T::BlockNumber) -> Option<(u32, u32)> {
+        Self::participation(who).last()
+            .and_then(|&(session, signed, relayed)| if session == index { Some((signed, relayed)) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::runtime_io::with_externalities;
+    use node_primitives::Hash;
+    use mock::{account, new_test_ext};
+    use {Runtime, Sigcount};
+
+    fn hash(seed: u8) -> Hash {
+        [seed; 32].into()
+    }
+
+    #[test]
+    fn counts_each_validator_once() {
+        with_externalities(&mut new_test_ext(), || {
+            <MinNumOfSignature<Runtime>>::put(3);
+            let tx = hash(1);
+            assert_err!(Sigcount::check_signature(account(1), tx, hash(10), tx), "Not enough signature!");
+            assert_err!(Sigcount::check_signature(account(1), tx, hash(11), tx), "This validator already signed!");
+            assert_eq!(Sigcount::num_of_signed(tx), 1);
+            assert_eq!(Sigcount::session_signed(&account(1)), 1);
+
+            assert_err!(Sigcount::check_signature(account(2), tx, hash(10), tx), "This signature is repeat!");
+            assert_err!(Sigcount::check_signature(account(2), tx, hash(20), tx), "Not enough signature!");
+            assert_ok!(Sigcount::check_signature(account(3), tx, hash(30), tx));
+            assert_eq!(Sigcount::num_of_signed(tx), 3);
+            assert_eq!(Sigcount::already_sent(tx), 1);
+            assert_eq!(Sigcount::session_relayed(), 1);
+            assert_eq!(Sigcount::session_signers(), vec![account(1), account(2), account(3)]);
+        });
+    }
+
+    #[test]
+    fn late_signatures_count_as_participation() {
+        with_externalities(&mut new_test_ext(), || {
+            let tx = hash(1);
+            assert_ok!(Sigcount::check_signature(account(1), tx, hash(10), tx));
+            assert_err!(Sigcount::check_signature(account(2), tx, hash(20), tx), "This Transcation already been sent!");
+            assert_err!(Sigcount::check_signature(account(2), tx, hash(21), tx), "This validator already signed!");
+            assert_eq!(Sigcount::num_of_signed(tx), 2);
+            assert_eq!(Sigcount::session_signed(&account(2)), 1);
+            // sent once
+            assert_eq!(Sigcount::session_relayed(), 1);
+        });
+    }
+}
@@ -1,7 +1,15 @@
+//! Vote pools sharing the relay rewards of a validator with its nominators.
+//!
+//! the pools are reward-only: bonding here does not register a staking intention,
+//! the session validators are still elected by `staking` from its own intentions.
+//! a validator joins a pool to be paid for its relay work by `reward_session`,
+//! and its nominators share that reward pro rata to their stake.
+
 //extern crate sr_primitives as primitives;
 extern crate sr_io as runtime_io;
 use rstd::prelude::Vec;
-use {balances, system::{self, ensure_signed}};
+use {balances, session, sigcount, staking, system::{self, ensure_signed}};
+use matrix::{self, Attestation};
 use srml_support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
 use runtime_primitives::traits::*;
 
@@ -11,11 +19,11 @@ use runtime_primitives::traits::*;
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct NominatorInfo<AccountId, Balance> {
     /// The stash account of the nominator in question.
-    who: AccountId,
+    pub who: AccountId,
     /// Amount of funds exposed.
-    value: Balance,
+    pub value: Balance,
     /// Amount of interest
-    interest: Balance,
+    pub interest: Balance,
 }
 
 
@@ -32,7 +40,10 @@ pub struct VotePool <Accountid , Balance>{
     pub others: Vec<NominatorInfo<Accountid, Balance>>,
 }
 
-pub trait Trait: balances::Trait + session::Trait + staking::Trait{
+/// Commission rates are given in percent of the reward.
+pub const MAX_COMMISSION: u32 = 100;
+
+pub trait Trait: balances::Trait + session::Trait + staking::Trait + sigcount::Trait + matrix::Trait{
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
@@ -40,11 +51,27 @@ decl_event!(
     pub enum Event<T>
     where
         <T as system::Trait>::AccountId,
-        <T as system::Trait>::Hash
+        <T as system::Trait>::Hash,
+        <T as balances::Trait>::Balance,
+        <T as system::Trait>::BlockNumber
     {
         Txisok(Hash,AccountId),
         // 交易 = vec<id，签名>
         //TranscationVerified(Hash,Vec<(AccountId,Hash)>),
+        /// A validator bonded its own stake: validator, bond, commission.
+        Validating(AccountId, Balance, u32),
+        /// A validator stopped validating, its pool is unbonding.
+        Unvalidated(AccountId),
+        /// A nominator backed a validator: nominator, validator, amount.
+        Nominated(AccountId, AccountId, Balance),
+        /// A nominator withdrew its stake, unlocked at the block.
+        Unnominated(AccountId, AccountId, BlockNumber),
+        /// Unbonded funds were released to an account.
+        Withdrawn(AccountId, Balance),
+        /// The reward of a session was paid to the pool of a validator.
+        PoolRewarded(AccountId, Balance),
+        /// A validator was slashed for a reported relay offence: validator, slashed, reporter.
+        Slashed(AccountId, Balance, AccountId),
     }
 );
 
@@ -61,6 +88,27 @@ decl_storage! {
 
        ///  validator ---  List of nominators (id,money)
        pub VoteInfo get(vote_pool): map T::AccountId => VotePool<T::AccountId,T::Balance>;
+
+        /// The accounts which declared to validate, each has a pool in `VoteInfo`.
+        pub Validators get(validators): Vec<T::AccountId>;
+        /// Percent of the pool reward a validator keeps before it is split.
+        pub Commission get(commission): map T::AccountId => u32;
+        /// The validator a nominator is backing.
+        pub Nominating get(nominating): map T::AccountId => Option<T::AccountId>;
+        /// Stakes being unbonded, (amount, block it is released at).
+        pub Unbonding get(unbonding): map T::AccountId => Vec<(T::Balance, T::BlockNumber)>;
+        /// Blocks a withdrawn stake stays reserved.
+        pub BondingDuration get(bonding_duration): T::BlockNumber = T::BlockNumber::sa(1000);
+        /// Reward of a validator pool for each session it validated.
+        pub SessionReward get(session_reward): T::Balance;
+        /// The last session pools were rewarded for.
+        LastRewardedSession get(last_rewarded_session): T::BlockNumber;
+        /// Percent of the own bond of a validator slashed for a relay offence.
+        pub SlashShare get(slash_share): u32 = 10;
+        /// Percent of a slash paid to the reporter of the offence.
+        pub ReporterShare get(reporter_share): u32 = 50;
+        /// Offences already punished, by the hash of the validator and the messages.
+        Reported get(reported): map T::Hash => bool;
     }
 }
 
@@ -70,30 +118,198 @@ decl_module! {
         fn deposit_event<T>() = default;
 
         /// 当验证者
-        pub fn validate(origin,money: T::Balance){
-            // 原本接口
+        /// Declare the desire to validate, bonding `money` of own stake and keeping
+        /// `commission` percent of the rewards. this opens a reward pool only, the
+        /// account still needs a staking intention to be elected.
+        pub fn validate(origin, money: T::Balance, commission: u32) -> Result {
             let who = ensure_signed(origin)?;
 			ensure!(<staking::Module<T>>::nominating(&who).is_none(), "Cannot stake if already nominating.");
-			let mut intentions = <staking::Module<T>>::intentions();
+			let intentions = <staking::Module<T>>::intentions();
 			// can't be in the list twice.
 			ensure!(intentions.iter().find(|&t| t == &who).is_none(), "Cannot stake if already staked.");
-    		//let bondage = <staking::Module<T>>::bondage();
-    		//bondage.insert(&who, T::BlockNumber::max_value());
-			//intentions.push(who);
-			//<Intentions<T>>::put(intentions);
-            // 新接口
+            ensure!(!<Validators<T>>::get().contains(&who), "Cannot validate if already validating.");
+            ensure!(Self::nominating(&who).is_none(), "Cannot validate if already nominating.");
+            ensure!(commission <= MAX_COMMISSION, "Commission is more than 100 percent.");
+            ensure!(!money.is_zero(), "Cannot validate without a bond.");
+
+            <balances::Module<T>>::reserve(&who, money)?;
+
+            <VoteInfo<T>>::insert(&who, VotePool {
+                total: money,
+                own: money,
+                interest: T::Balance::zero(),
+                others: Vec::new(),
+            });
+            <Commission<T>>::insert(&who, commission);
+            <Validators<T>>::mutate(|v| v.push(who.clone()));
+
+            Self::deposit_event(RawEvent::Validating(who, money, commission));
+            Ok(())
         }
 
-        fn nominate(origin, target: <T::Lookup as StaticLookup>::Source){
+        /// Back `target` with `money`, shared in the rewards of its pool.
+        pub fn nominate(origin, target: <T::Lookup as StaticLookup>::Source, money: T::Balance) -> Result {
+            let who = ensure_signed(origin)?;
+            let target = T::Lookup::lookup(target)?;
+            ensure!(<Validators<T>>::get().contains(&target), "Cannot nominate an account not validating.");
+            ensure!(!<Validators<T>>::get().contains(&who), "Cannot nominate if validating.");
+            ensure!(Self::nominating(&who).is_none(), "Cannot nominate if already nominating.");
+            ensure!(!money.is_zero(), "Cannot nominate without a bond.");
+
+            <balances::Module<T>>::reserve(&who, money)?;
+
+            <VoteInfo<T>>::mutate(&target, |pool| {
+                pool.total += money;
+                pool.others.push(NominatorInfo {
+                    who: who.clone(),
+                    value: money,
+                    interest: T::Balance::zero(),
+                });
+            });
+            <Nominating<T>>::insert(&who, target.clone());
 
+            Self::deposit_event(RawEvent::Nominated(who, target, money));
+            Ok(())
         }
 
-        fn unvalidate(origin){
+        /// Stop validating, the own stake and the stakes of all nominators start unbonding.
+        pub fn unvalidate(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            ensure!(<Validators<T>>::get().contains(&who), "Cannot unvalidate if not validating.");
+
+            let pool = <VoteInfo<T>>::take(&who);
+            let until = Self::unlock_at();
+            Self::unbond(&who, pool.own, until);
+            for nominator in pool.others {
+                <Nominating<T>>::remove(&nominator.who);
+                Self::unbond(&nominator.who, nominator.value, until);
+            }
+            <Commission<T>>::remove(&who);
+            <Validators<T>>::mutate(|v| v.retain(|x| x != &who));
 
+            Self::deposit_event(RawEvent::Unvalidated(who));
+            Ok(())
         }
 
-        fn unnominate(origin){
+        /// Withdraw the nomination, the stake is released after the bonding duration.
+        pub fn unnominate(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            let target = Self::nominating(&who).ok_or("Cannot unnominate if not nominating.")?;
 
+            let mut pool = <VoteInfo<T>>::get(&target);
+            let index = pool.others.iter().position(|n| n.who == who)
+                .ok_or("Nominator is not in the pool of the validator.")?;
+            let nominator = pool.others.remove(index);
+            pool.total -= nominator.value;
+            <VoteInfo<T>>::insert(&target, pool);
+            <Nominating<T>>::remove(&who);
+
+            let until = Self::unlock_at();
+            Self::unbond(&who, nominator.value, until);
+
+            Self::deposit_event(RawEvent::Unnominated(who, target, until));
+            Ok(())
+        }
+
+        /// Release the stakes whose bonding duration passed.
+        pub fn withdraw_unbonded(origin) -> Result {
+            let who = ensure_signed(origin)?;
+            let now = <system::Module<T>>::block_number();
+
+            let (ready, pending): (Vec<_>, Vec<_>) = Self::unbonding(&who)
+                .into_iter()
+                .partition(|&(_, until)| until <= now);
+            ensure!(!ready.is_empty(), "No unbonded stake to withdraw.");
+
+            let amount = ready.into_iter().fold(T::Balance::zero(), |sum, (value, _)| sum + value);
+            <balances::Module<T>>::unreserve(&who, amount);
+            if pending.is_empty() {
+                <Unbonding<T>>::remove(&who);
+            } else {
+                <Unbonding<T>>::insert(&who, pending);
+            }
+
+            Self::deposit_event(RawEvent::Withdrawn(who, amount));
+            Ok(())
+        }
+
+        /// Report a validator attesting two conflicting versions of a relay message,
+        /// see `matrix::Module::conflicting`. both attestations are signed with its session key.
+        /// authority sets are not covered: they are never attested and
+        /// `matrix::reset_authorities` applies none, two sets relayed for one Ethereum
+        /// block can't be reported yet.
+        pub fn report_equivocation(
+            origin,
+            first: Attestation<T::SessionKey>,
+            first_proof: Vec<u8>,
+            second: Attestation<T::SessionKey>,
+            second_proof: Vec<u8>
+        ) -> Result {
+            let reporter = ensure_signed(origin)?;
+            ensure!(first.validator == second.validator, "attestations of different validators");
+            ensure!(first.kind == second.kind, "attestations of different kinds");
+            let validator = <matrix::Module<T>>::check_attestation(&first, &first_proof)?;
+            <matrix::Module<T>>::check_attestation(&second, &second_proof)?;
+
+            let (a, b) = first.messages.iter()
+                .flat_map(|(a, _)| second.messages.iter().map(move |(b, _)| (a, b)))
+                .find(|(a, b)| <matrix::Module<T>>::conflicting(a, b))
+                .ok_or("attestations don't conflict")?;
+            // the same offence whatever the order of the attestations
+            let offence = if a < b {
+                T::Hashing::hash_of(&(validator.clone(), a, b))
+            } else {
+                T::Hashing::hash_of(&(validator.clone(), b, a))
+            };
+            Self::punish(validator, reporter, offence)
+        }
+
+        /// Report a validator attesting the message at `index` of `attestation`
+        /// that more than two thirds of the authorities rejected.
+        pub fn report_rejected(
+            origin,
+            attestation: Attestation<T::SessionKey>,
+            proof: Vec<u8>,
+            index: u32,
+            rejections: Vec<(T::SessionKey, Vec<u8>)>
+        ) -> Result {
+            let reporter = ensure_signed(origin)?;
+            let validator = <matrix::Module<T>>::check_attestation(&attestation, &proof)?;
+            let (message, _) = attestation.messages.get(index as usize).ok_or("no message at index")?;
+            let hash = T::Hashing::hash_of(message);
+            <matrix::Module<T>>::check_rejection(attestation.kind, hash, &rejections)?;
+
+            let offence = T::Hashing::hash_of(&(validator.clone(), hash));
+            Self::punish(validator, reporter, offence)
+        }
+
+        /// set the percent of the own bond slashed and the percent of it paid to the reporter
+        fn set_slash_shares(slash: u32, reporter: u32) -> Result {
+            ensure!(slash <= 100 && reporter <= 100, "shares are percents");
+            <SlashShare<T>>::put(slash);
+            <ReporterShare<T>>::put(reporter);
+            Ok(())
+        }
+
+        /// set the blocks a withdrawn stake stays reserved
+        fn set_bonding_duration(duration: T::BlockNumber) {
+            <BondingDuration<T>>::put(duration);
+        }
+
+        /// set the reward of a validator pool per session
+        fn set_session_reward(reward: T::Balance) {
+            <SessionReward<T>>::put(reward);
+        }
+
+        /// reward the pools of the session validators once the session changed
+        fn on_finalise(_n: T::BlockNumber) {
+            let index = <session::Module<T>>::current_index();
+            let ended = Self::last_rewarded_session();
+            if index != ended {
+                <sigcount::Module<T>>::end_session(ended);
+                <LastRewardedSession<T>>::put(index);
+                Self::reward_session(ended);
+            }
         }
     }
 }
@@ -104,5 +320,354 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Rewards paid to `who` as validator or nominator of its current pool.
+    pub fn interest_of(who: &T::AccountId) -> T::Balance {
+        if <VoteInfo<T>>::exists(who) {
+            return Self::vote_pool(who).interest;
+        }
+        Self::nominating(who)
+            .and_then(|target| Self::vote_pool(&target).others.into_iter().find(|n| &n.who == who))
+            .map_or_else(Zero::zero, |nominator| nominator.interest)
+    }
+
+    fn unlock_at() -> T::BlockNumber {
+        <system::Module<T>>::block_number() + Self::bonding_duration()
+    }
+
+    fn unbond(who: &T::AccountId, value: T::Balance, until: T::BlockNumber) {
+        <Unbonding<T>>::mutate(who, |unbonding| unbonding.push((value, until)));
+    }
+
+    /// Slash `SlashShare` of the own bond of `validator` once for `offence`, the
+    /// bonded one and the one still unbonding, pay `ReporterShare` of it to
+    /// `reporter`, and report the validator to `staking` the way
+    /// `aura::StakingSlasher` does.
+    fn punish(validator: T::AccountId, reporter: T::AccountId, offence: T::Hash) -> Result {
+        ensure!(!Self::reported(offence), "offence already reported");
+        <Reported<T>>::insert(offence, true);
+
+        let share = T::Balance::sa(Self::slash_share() as u64);
+        let mut slashed = T::Balance::zero();
+        if <VoteInfo<T>>::exists(&validator) {
+            let mut pool = Self::vote_pool(&validator);
+            let amount = pool.own * share / T::Balance::sa(100);
+            let remaining = <balances::Module<T>>::slash_reserved(&validator, amount).unwrap_or_else(Zero::zero);
+            slashed = amount - remaining;
+            pool.own -= slashed;
+            pool.total -= slashed;
+            <VoteInfo<T>>::insert(&validator, pool);
+        }
+        // leaving the pool right after the offence doesn't escape it.
+        slashed += Self::slash_unbonding(&validator, share);
+        <staking::Module<T>>::on_offline_validator(validator.clone(), 1);
+
+        let bounty = slashed * T::Balance::sa(Self::reporter_share() as u64) / T::Balance::sa(100);
+        let _ = <balances::Module<T>>::reward(&reporter, bounty);
+
+        Self::deposit_event(RawEvent::Slashed(validator, slashed, reporter));
+        Ok(())
+    }
 
-}
\ No newline at end of file
+    /// Slash `share` percent of each stake `who` is unbonding and not released yet,
+    /// and return the amount slashed.
+    fn slash_unbonding(who: &T::AccountId, share: T::Balance) -> T::Balance {
+        let now = <system::Module<T>>::block_number();
+        let mut unbonding = Self::unbonding(who);
+        let mut slashed = T::Balance::zero();
+        for entry in unbonding.iter_mut().filter(|entry| entry.1 > now) {
+            let amount = entry.0 * share / T::Balance::sa(100);
+            let remaining = <balances::Module<T>>::slash_reserved(who, amount).unwrap_or_else(Zero::zero);
+            entry.0 -= amount - remaining;
+            slashed += amount - remaining;
+        }
+        if !slashed.is_zero() {
+            <Unbonding<T>>::insert(who, unbonding);
+        }
+        slashed
+    }
+
+    /// Pay the reward of session `index` to every pool whose validator is in the
+    /// session, scaled by the share of relayed messages the validator co-signed.
+    fn reward_session(index: T::BlockNumber) {
+        let reward = Self::session_reward();
+        if reward.is_zero() {
+            return;
+        }
+        let session_validators = <session::Module<T>>::validators();
+        for validator in Self::validators().into_iter().filter(|v| session_validators.contains(v)) {
+            let reward = match <sigcount::Module<T>>::last_participation(&validator, index) {
+                // no bridge traffic in the session, nothing to take part in
+                Some((_, 0)) | None => reward,
+                Some((signed, relayed)) => reward * T::Balance::sa(signed.min(relayed) as u64) / T::Balance::sa(relayed as u64),
+            };
+            Self::distribute_reward(&validator, reward);
+        }
+    }
+
+    /// Split `reward` of the pool of `validator`: the commission goes to the validator,
+    /// the rest pro rata to the own stake and the stake of every nominator.
+    pub fn distribute_reward(validator: &T::AccountId, reward: T::Balance) {
+        let mut pool = Self::vote_pool(validator);
+        if pool.total.is_zero() {
+            return;
+        }
+        let commission = reward * T::Balance::sa(Self::commission(validator) as u64) / T::Balance::sa(MAX_COMMISSION as u64);
+        let rest = reward - commission;
+
+        let own = commission + rest * pool.own / pool.total;
+        let _ = <balances::Module<T>>::reward(validator, own);
+        pool.interest += own;
+
+        for nominator in pool.others.iter_mut() {
+            let share = rest * nominator.value / pool.total;
+            let _ = <balances::Module<T>>::reward(&nominator.who, share);
+            nominator.interest += share;
+        }
+        <VoteInfo<T>>::insert(validator, pool);
+
+        Self::deposit_event(RawEvent::PoolRewarded(validator.clone(), reward));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use self::runtime_io::with_externalities;
+    use mock::{account, endow, new_test_ext, pair, session_key, set_validators};
+    use node_primitives::SessionKey;
+    use codec::Encode;
+    use {Balances, Origin, Runtime, Sigcount, System, Vote};
+
+    /// validator 1 bonds 600 with a 10 percent commission, nominator 2 backs it with 400.
+    fn pool() {
+        endow(1, 1000);
+        endow(2, 1000);
+        assert_ok!(Vote::validate(Origin::signed(account(1)), 600, 10));
+        assert_ok!(Vote::nominate(Origin::signed(account(2)), account(1).into(), 400));
+    }
+
+    /// An ingress message of chain 1 from transaction 1 to `recipient`.
+    fn message(recipient: u8, value: u8) -> Vec<u8> {
+        let mut message = vec![1u8; matrix::MESSAGE_LENGTH];
+        message[32..52].copy_from_slice(&[recipient; 20]);
+        message[52..84].copy_from_slice(&[value; 32]);
+        message
+    }
+
+    /// `messages` attested by validator `seed`, with the proof of its session key.
+    fn attestation(seed: u8, messages: Vec<Vec<u8>>) -> (Attestation<SessionKey>, Vec<u8>) {
+        let attestation = Attestation {
+            validator: session_key(seed),
+            kind: 1,
+            messages: messages.into_iter().map(|message| (message, Vec::new())).collect(),
+        };
+        let proof = pair(seed).sign(&matrix::attestation_payload(&attestation)).0.to_vec();
+        (attestation, proof)
+    }
+
+    /// Rejections of `message` by the validators `seeds`.
+    fn rejections(seeds: &[u8], message: &[u8]) -> Vec<(SessionKey, Vec<u8>)> {
+        let payload = (matrix::REJECTION_TAG, 1u8, BlakeTwo256::hash_of(&message.to_vec())).encode();
+        seeds.iter().map(|seed| (session_key(*seed), pair(*seed).sign(&payload).0.to_vec())).collect()
+    }
+
+    /// validators 1 to 4, validator 1 with a pool bonding 600.
+    fn validators() {
+        set_validators(&[1, 2, 3, 4]);
+        endow(1, 1000);
+        assert_ok!(Vote::validate(Origin::signed(account(1)), 600, 10));
+    }
+
+    #[test]
+    fn punish_slashes_own_bond_and_pays_reporter() {
+        with_externalities(&mut new_test_ext(), || {
+            validators();
+            let offence = BlakeTwo256::hash_of(&1u8);
+            assert_ok!(Vote::punish(account(1), account(5), offence));
+            // 10 percent of 600, half of it to the reporter.
+            assert_eq!(Balances::reserved_balance(&account(1)), 540);
+            assert_eq!(Balances::free_balance(&account(5)), 30);
+            let pool = Vote::vote_pool(&account(1));
+            assert_eq!((pool.own, pool.total), (540, 540));
+            assert_noop!(Vote::punish(account(1), account(5), offence), "offence already reported");
+        });
+    }
+
+    #[test]
+    fn punish_slashes_unbonding_bond() {
+        with_externalities(&mut new_test_ext(), || {
+            validators();
+            endow(2, 1000);
+            assert_ok!(Vote::nominate(Origin::signed(account(2)), account(1).into(), 400));
+            assert_ok!(Vote::unvalidate(Origin::signed(account(1))));
+            assert_ok!(Vote::punish(account(1), account(5), BlakeTwo256::hash_of(&1u8)));
+            let until = Vote::bonding_duration();
+            assert_eq!(Vote::unbonding(&account(1)), vec![(540, until)]);
+            assert_eq!(Balances::reserved_balance(&account(1)), 540);
+            assert_eq!(Balances::free_balance(&account(5)), 30);
+            // nominators are not slashed.
+            assert_eq!(Vote::unbonding(&account(2)), vec![(400, until)]);
+
+            // released stakes are not.
+            System::set_block_number(until);
+            assert_ok!(Vote::punish(account(1), account(5), BlakeTwo256::hash_of(&2u8)));
+            assert_eq!(Balances::reserved_balance(&account(1)), 540);
+            assert_ok!(Vote::withdraw_unbonded(Origin::signed(account(1))));
+            assert_eq!(Balances::free_balance(&account(1)), 940);
+        });
+    }
+
+    #[test]
+    fn report_equivocation_once() {
+        with_externalities(&mut new_test_ext(), || {
+            validators();
+            let (first, first_proof) = attestation(1, vec![message(2, 3)]);
+            let (second, second_proof) = attestation(1, vec![message(2, 4)]);
+            let (other, other_proof) = attestation(1, vec![message(5, 4)]);
+            assert_noop!(
+                Vote::report_equivocation(Origin::signed(account(5)), first.clone(), first_proof.clone(), other, other_proof),
+                "attestations don't conflict"
+            );
+            assert_noop!(
+                Vote::report_equivocation(Origin::signed(account(5)), first.clone(), second_proof.clone(), second.clone(), second_proof.clone()),
+                "invalid attestation proof"
+            );
+            assert_ok!(Vote::report_equivocation(Origin::signed(account(5)), first.clone(), first_proof.clone(), second.clone(), second_proof.clone()));
+            assert_eq!(Balances::reserved_balance(&account(1)), 540);
+            // the same offence in the other order
+            assert_noop!(
+                Vote::report_equivocation(Origin::signed(account(6)), second, second_proof, first, first_proof),
+                "offence already reported"
+            );
+        });
+    }
+
+    #[test]
+    fn report_rejected_needs_a_quorum() {
+        with_externalities(&mut new_test_ext(), || {
+            validators();
+            let (attested, proof) = attestation(1, vec![message(2, 3)]);
+            assert_noop!(
+                Vote::report_rejected(Origin::signed(account(5)), attested.clone(), proof.clone(), 0, rejections(&[2, 3], &message(2, 3))),
+                "message rejected without a quorum"
+            );
+            assert_noop!(
+                Vote::report_rejected(Origin::signed(account(5)), attested.clone(), proof.clone(), 1, rejections(&[2, 3, 4], &message(2, 3))),
+                "no message at index"
+            );
+            assert_noop!(
+                Vote::report_rejected(Origin::signed(account(5)), attested.clone(), proof.clone(), 0, rejections(&[2, 3, 4], &message(2, 4))),
+                "invalid rejection proof"
+            );
+            assert_ok!(Vote::report_rejected(Origin::signed(account(5)), attested.clone(), proof.clone(), 0, rejections(&[2, 3, 4], &message(2, 3))));
+            assert_eq!(Balances::free_balance(&account(5)), 30);
+            assert_noop!(
+                Vote::report_rejected(Origin::signed(account(6)), attested, proof, 0, rejections(&[2, 3, 4], &message(2, 3))),
+                "offence already reported"
+            );
+        });
+    }
+
+    #[test]
+    fn validate_bonds_own_stake() {
+        with_externalities(&mut new_test_ext(), || {
+            endow(1, 1000);
+            assert_noop!(Vote::validate(Origin::signed(account(1)), 600, 101), "Commission is more than 100 percent.");
+            assert!(Vote::validate(Origin::signed(account(1)), 2000, 10).is_err());
+            assert_ok!(Vote::validate(Origin::signed(account(1)), 600, 10));
+            assert_eq!(Balances::reserved_balance(&account(1)), 600);
+            assert_eq!(Vote::validators(), vec![account(1)]);
+            assert_eq!(Vote::commission(&account(1)), 10);
+            let pool = Vote::vote_pool(&account(1));
+            assert_eq!((pool.own, pool.total), (600, 600));
+            assert_noop!(Vote::validate(Origin::signed(account(1)), 100, 10), "Cannot validate if already validating.");
+        });
+    }
+
+    #[test]
+    fn nominate_backs_pool() {
+        with_externalities(&mut new_test_ext(), || {
+            pool();
+            assert_eq!(Balances::reserved_balance(&account(2)), 400);
+            assert_eq!(Vote::nominating(&account(2)), Some(account(1)));
+            let pool = Vote::vote_pool(&account(1));
+            assert_eq!((pool.own, pool.total), (600, 1000));
+            assert_eq!(pool.others.len(), 1);
+            assert_eq!((pool.others[0].who, pool.others[0].value), (account(2), 400));
+            endow(3, 1000);
+            assert_noop!(Vote::nominate(Origin::signed(account(3)), account(2).into(), 100), "Cannot nominate an account not validating.");
+        });
+    }
+
+    #[test]
+    fn unnominate_unbonds_after_bonding_duration() {
+        with_externalities(&mut new_test_ext(), || {
+            pool();
+            System::set_block_number(10);
+            assert_ok!(Vote::unnominate(Origin::signed(account(2))));
+            let until = 10 + Vote::bonding_duration();
+            assert_eq!(Vote::unbonding(&account(2)), vec![(400, until)]);
+            assert_eq!(Vote::nominating(&account(2)), None);
+            assert_eq!(Vote::vote_pool(&account(1)).total, 600);
+
+            // still reserved until the end of the bonding duration.
+            assert_noop!(Vote::withdraw_unbonded(Origin::signed(account(2))), "No unbonded stake to withdraw.");
+            System::set_block_number(until);
+            assert_ok!(Vote::withdraw_unbonded(Origin::signed(account(2))));
+            assert_eq!(Balances::reserved_balance(&account(2)), 0);
+            assert_eq!(Balances::free_balance(&account(2)), 1000);
+            assert!(Vote::unbonding(&account(2)).is_empty());
+        });
+    }
+
+    #[test]
+    fn unvalidate_unbonds_the_pool() {
+        with_externalities(&mut new_test_ext(), || {
+            pool();
+            assert_ok!(Vote::unvalidate(Origin::signed(account(1))));
+            assert!(Vote::validators().is_empty());
+            assert_eq!(Vote::nominating(&account(2)), None);
+            let until = Vote::bonding_duration();
+            assert_eq!(Vote::unbonding(&account(1)), vec![(600, until)]);
+            assert_eq!(Vote::unbonding(&account(2)), vec![(400, until)]);
+        });
+    }
+
+    #[test]
+    fn distribute_reward_pro_rata_after_commission() {
+        with_externalities(&mut new_test_ext(), || {
+            pool();
+            Vote::distribute_reward(&account(1), 1000);
+            // 100 of commission, then 900 split 600 / 400.
+            assert_eq!(Balances::free_balance(&account(1)), 400 + 100 + 540);
+            assert_eq!(Balances::free_balance(&account(2)), 600 + 360);
+            assert_eq!(Vote::interest_of(&account(1)), 640);
+            assert_eq!(Vote::interest_of(&account(2)), 360);
+        });
+    }
+
+    #[test]
+    fn validators_relaying_the_same_messages_are_both_rewarded() {
+        with_externalities(&mut new_test_ext(), || {
+            set_validators(&[1, 2]);
+            endow(1, 1000);
+            endow(2, 1000);
+            assert_ok!(Vote::validate(Origin::signed(account(1)), 600, 0));
+            assert_ok!(Vote::validate(Origin::signed(account(2)), 600, 0));
+            <SessionReward<Runtime>>::put(100);
+            // validator 2 signs every message after validator 1 reached the threshold.
+            for tx in 1..3u8 {
+                let hash = BlakeTwo256::hash_of(&tx);
+                assert_ok!(Sigcount::check_signature(account(1), hash, BlakeTwo256::hash_of(&(tx, 1u8)), hash));
+                assert_err!(
+                    Sigcount::check_signature(account(2), hash, BlakeTwo256::hash_of(&(tx, 2u8)), hash),
+                    "This Transcation already been sent!"
+                );
+            }
+            Sigcount::end_session(0);
+            Vote::reward_session(0);
+            assert_eq!(Vote::interest_of(&account(1)), 100);
+            assert_eq!(Vote::interest_of(&account(2)), 100);
+        });
+    }
+}